@@ -0,0 +1,246 @@
+//! # MACI Verifier
+//!
+//! Off-chain counting of the MACI (Minimal Anti-Collusion Infrastructure) polls of pallet-qv.
+//!
+//! Voters encrypt [`Command`]s to the coordinator's X25519 key with [`encrypt`], and publish them
+//! with `publish_message`. The coordinator runs [`process`] on the message log and publishes the
+//! resulting tally and commitment with `publish_maci_tally`, which stores them with a
+//! [`tally_commitment`] over the poll's message root and voice credits.
+//!
+//! The chain can not check the tally, and the commitment to the voter states is salted with the
+//! coordinator's secret key. Only whoever holds that key can run [`process`] on the same log, and
+//! check that the stored tally commitment matches. Anyone else can only check that the tally
+//! commitment is over the poll's message root.
+//!
+//! > NOTE: The encryption is a Blake2 keystream over an X25519 shared secret, without
+//! > authentication. A tampered message fails to decode or to verify, and is skipped like any
+//! > other invalid message.
+
+use codec::{Decode, Encode};
+use sp_core::{ed25519, hashing::blake2_256, Pair, H256};
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+#[cfg(test)]
+mod tests;
+
+/// A vote, a key change, or both, signed by a voter and encrypted to the coordinator.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct Command {
+	/// The voter's state index, as emitted in `MaciSignedUp`.
+	pub state_index: u32,
+	/// The number of the voter's valid commands so far, plus one.
+	pub nonce: u32,
+	/// The key that the voter's next command must be signed with. The current key, unless the
+	/// command changes it.
+	pub new_public_key: [u8; 32],
+	/// Whether the votes are in favour.
+	pub aye: bool,
+	/// The voter's votes, which replace those of its earlier commands. Zero withdraws them.
+	pub votes: u32,
+	/// The signature of the rest of the command and the referendum index, by the voter's current
+	/// key.
+	pub signature: [u8; 64],
+}
+
+impl Command {
+	/// A command on referendum `index`, signed with the voter's current `key`.
+	pub fn signed(
+		index: u32,
+		state_index: u32,
+		nonce: u32,
+		new_public_key: [u8; 32],
+		aye: bool,
+		votes: u32,
+		key: &ed25519::Pair,
+	) -> Self {
+		let mut command =
+			Command { state_index, nonce, new_public_key, aye, votes, signature: [0; 64] };
+		command.signature = key.sign(&command.payload(index)).0;
+		command
+	}
+
+	/// What the signature is over.
+	fn payload(&self, index: u32) -> Vec<u8> {
+		(index, self.state_index, self.nonce, self.new_public_key, self.aye, self.votes).encode()
+	}
+
+	/// Whether the command on referendum `index` is signed with `public_key`.
+	fn is_signed_by(&self, index: u32, public_key: &[u8; 32]) -> bool {
+		ed25519::Pair::verify(
+			&ed25519::Signature(self.signature),
+			self.payload(index),
+			&ed25519::Public(*public_key),
+		)
+	}
+}
+
+/// The X25519 public key of `secret_key`.
+pub fn public_key(secret_key: [u8; 32]) -> [u8; 32] {
+	PublicKey::from(&StaticSecret::from(secret_key)).to_bytes()
+}
+
+/// Encrypts `command` to the coordinator's `public_key`, with a secret key that is only used for
+/// this message. Returns the message's ephemeral public key and data.
+pub fn encrypt(
+	command: &Command,
+	public_key: [u8; 32],
+	ephemeral_secret: [u8; 32],
+) -> ([u8; 32], Vec<u8>) {
+	let secret = StaticSecret::from(ephemeral_secret);
+	let ephemeral_key = PublicKey::from(&secret).to_bytes();
+	let shared = secret.diffie_hellman(&PublicKey::from(public_key));
+	(ephemeral_key, apply_keystream(shared.as_bytes(), command.encode()))
+}
+
+/// Decrypts a message with the coordinator's `secret_key`, or `None` if it is not a command.
+pub fn decrypt(secret_key: [u8; 32], ephemeral_key: [u8; 32], data: &[u8]) -> Option<Command> {
+	let shared = StaticSecret::from(secret_key).diffie_hellman(&PublicKey::from(ephemeral_key));
+	let plain = apply_keystream(shared.as_bytes(), data.to_vec());
+	Command::decode(&mut &plain[..]).ok()
+}
+
+/// XORs `data` with the Blake2 keystream of `shared_secret`.
+fn apply_keystream(shared_secret: &[u8; 32], mut data: Vec<u8>) -> Vec<u8> {
+	for (block, chunk) in data.chunks_mut(32).enumerate() {
+		let keystream = blake2_256(&(shared_secret, block as u32).encode());
+		chunk.iter_mut().zip(keystream).for_each(|(byte, key)| *byte ^= key);
+	}
+	data
+}
+
+/// The sign ups and messages of a MACI poll, as stored in `MaciVoterKeys` and `MaciMessages`.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+pub struct MessageLog {
+	/// The index of the referendum.
+	pub index: u32,
+	/// The voice credits of every voter, the runtime's `MaciVoiceCredits`.
+	pub voice_credits: u32,
+	/// The public keys that voters signed up with, by state index.
+	pub signups: Vec<[u8; 32]>,
+	/// The ephemeral public key and the data of every message, in order.
+	pub messages: Vec<([u8; 32], Vec<u8>)>,
+}
+
+impl MessageLog {
+	/// The hash chain of the messages, which must match `MaciPoll::message_root` on chain.
+	pub fn message_root(&self) -> H256 {
+		self.messages.iter().fold(H256::default(), |root, (ephemeral_key, data)| {
+			BlakeTwo256::hash_of(&(root, ephemeral_key, data))
+		})
+	}
+}
+
+/// A voter's key, nonce and vote after processing its valid commands.
+#[derive(Encode, Clone, PartialEq, Eq, Debug)]
+pub struct VoterState {
+	/// The key that the voter's next command must be signed with.
+	pub public_key: [u8; 32],
+	/// The nonce of the voter's last valid command.
+	pub nonce: u32,
+	/// Whether the votes are in favour.
+	pub aye: bool,
+	/// The votes of the voter's last valid command.
+	pub votes: u32,
+}
+
+/// The tally of a MACI poll, and what it was counted from.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Tally {
+	/// The number of votes in favour.
+	pub ayes: u32,
+	/// The number of votes against.
+	pub nays: u32,
+	/// The number of voters in favour.
+	pub supporters: u32,
+	/// The hash chain of the messages that were processed.
+	pub message_root: H256,
+	/// The commitment to the final voter states, which `publish_maci_tally` is called with.
+	pub commitment: H256,
+	/// The commitment that `publish_maci_tally` stores along with the tally, see
+	/// [`tally_commitment`].
+	pub tally_commitment: H256,
+}
+
+/// The commitment to the message root and the voice credits of poll `index`, its tally, and the
+/// salted `commitment` to the voter states, as stored on chain by `publish_maci_tally`.
+pub fn tally_commitment(
+	index: u32,
+	message_root: H256,
+	voice_credits: u32,
+	ayes: u32,
+	nays: u32,
+	supporters: u32,
+	commitment: H256,
+) -> H256 {
+	BlakeTwo256::hash_of(&(index, message_root, voice_credits, ayes, nays, supporters, commitment))
+}
+
+/// Decrypts and processes the commands of `log` in order, and counts the last valid vote of every
+/// voter.
+///
+/// A command is valid if it decrypts, refers to a voter that signed up, is signed with the voter's
+/// current key, carries the next nonce, and its quadratic price is within the log's voice credits.
+/// Every other message is skipped, so a voter that changed its key has any later command signed
+/// with the old key ignored, without anyone but the coordinator knowing.
+///
+/// The commitment is salted with the coordinator's secret key, so that it gives nothing away
+/// about the voter states to whoever does not have the key. That also means that only whoever
+/// has the key can recompute it.
+pub fn process(log: &MessageLog, secret_key: [u8; 32]) -> Tally {
+	let mut states: Vec<VoterState> = log
+		.signups
+		.iter()
+		.map(|&public_key| VoterState { public_key, nonce: 0, aye: false, votes: 0 })
+		.collect();
+
+	for (ephemeral_key, data) in &log.messages {
+		let command = match decrypt(secret_key, *ephemeral_key, data) {
+			Some(command) => command,
+			None => continue,
+		};
+		let state = match states.get_mut(command.state_index as usize) {
+			Some(state) => state,
+			None => continue,
+		};
+		let affordable = (command.votes as u64)
+			.checked_mul(command.votes as u64)
+			.map_or(false, |price| price <= log.voice_credits as u64);
+		if !affordable ||
+			state.nonce.checked_add(1) != Some(command.nonce) ||
+			!command.is_signed_by(log.index, &state.public_key)
+		{
+			continue
+		}
+		*state = VoterState {
+			public_key: command.new_public_key,
+			nonce: command.nonce,
+			aye: command.aye,
+			votes: command.votes,
+		};
+	}
+
+	let (mut ayes, mut nays, mut supporters) = (0u32, 0u32, 0u32);
+	for state in states.iter().filter(|state| state.votes > 0) {
+		if state.aye {
+			ayes = ayes.saturating_add(state.votes);
+			supporters += 1;
+		} else {
+			nays = nays.saturating_add(state.votes);
+		}
+	}
+	let message_root = log.message_root();
+	let salt = blake2_256(&(secret_key, log.index).encode());
+	let commitment =
+		BlakeTwo256::hash_of(&(log.index, message_root, &states, ayes, nays, supporters, salt));
+	let tally_commitment = tally_commitment(
+		log.index,
+		message_root,
+		log.voice_credits,
+		ayes,
+		nays,
+		supporters,
+		commitment,
+	);
+	Tally { ayes, nays, supporters, message_root, commitment, tally_commitment }
+}
@@ -0,0 +1,50 @@
+//! Recomputes the tally of a MACI poll of pallet-qv from its message log.
+//!
+//! Usage: `maci-verifier <message-log> <coordinator-secret-key>`
+//!
+//! - `message-log`: A file holding the SCALE encoded `MessageLog` of the poll, as read from
+//!   `MaciVoterKeys`, `MaciMessages` and the `MaciVoiceCredits` constant.
+//! - `coordinator-secret-key`: The coordinator's X25519 secret key, in hex.
+//!
+//! Prints the tally and the commitment that `publish_maci_tally` is called with, the message root
+//! that the poll's `message_root` must match, and the tally commitment that the published tally's
+//! `tally_commitment` must match.
+
+use codec::Decode;
+use maci_verifier::{process, MessageLog, Tally};
+use std::{env, fs, process::exit};
+
+fn main() {
+	let args: Vec<String> = env::args().collect();
+	if args.len() != 3 {
+		let program = args.first().map_or("maci-verifier", String::as_str);
+		eprintln!("Usage: {} <message-log> <coordinator-secret-key>", program);
+		exit(2)
+	}
+
+	match run(&args[1], &args[2]) {
+		Ok(tally) => {
+			println!("ayes: {}", tally.ayes);
+			println!("nays: {}", tally.nays);
+			println!("supporters: {}", tally.supporters);
+			println!("message root: {:?}", tally.message_root);
+			println!("commitment: {:?}", tally.commitment);
+			println!("tally commitment: {:?}", tally.tally_commitment);
+		},
+		Err(error) => {
+			eprintln!("Error: {}", error);
+			exit(1)
+		},
+	}
+}
+
+fn run(log_path: &str, secret_key: &str) -> Result<Tally, String> {
+	let bytes = fs::read(log_path).map_err(|error| format!("{}: {}", log_path, error))?;
+	let log = MessageLog::decode(&mut &bytes[..])
+		.map_err(|error| format!("{} is not a message log: {}", log_path, error))?;
+	let secret_key: [u8; 32] = sp_core::bytes::from_hex(secret_key)
+		.ok()
+		.and_then(|key| key.try_into().ok())
+		.ok_or("The coordinator's secret key must be 32 bytes in hex")?;
+	Ok(process(&log, secret_key))
+}
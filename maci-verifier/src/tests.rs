@@ -0,0 +1,120 @@
+use crate::*;
+
+const COORDINATOR_SECRET: [u8; 32] = [9; 32];
+const INDEX: u32 = 0;
+
+fn voter_key(seed: u8) -> ed25519::Pair {
+	ed25519::Pair::from_seed(&[seed; 32])
+}
+
+/// A log with the sign ups of voters with keys from seeds `1..=voters`, who have 100 voice credits
+/// each.
+fn log_with_voters(voters: u8) -> MessageLog {
+	MessageLog {
+		index: INDEX,
+		voice_credits: 100,
+		signups: (1..=voters).map(|seed| voter_key(seed).public().0).collect(),
+		messages: Vec::new(),
+	}
+}
+
+/// A vote on `INDEX` that keeps the key from `seed`.
+fn vote(state_index: u32, nonce: u32, seed: u8, aye: bool, votes: u32) -> Command {
+	let key = voter_key(seed);
+	Command::signed(INDEX, state_index, nonce, key.public().0, aye, votes, &key)
+}
+
+fn publish(log: &mut MessageLog, command: &Command) {
+	let ephemeral_secret = [log.messages.len() as u8 + 100; 32];
+	let message = encrypt(command, public_key(COORDINATOR_SECRET), ephemeral_secret);
+	log.messages.push(message);
+}
+
+#[test]
+fn message_decrypts_with_coordinator_key_only() {
+	let command = vote(0, 1, 1, true, 3);
+	let (ephemeral_key, data) = encrypt(&command, public_key(COORDINATOR_SECRET), [7; 32]);
+
+	assert_eq!(decrypt(COORDINATOR_SECRET, ephemeral_key, &data), Some(command.clone()));
+	assert_ne!(decrypt([8; 32], ephemeral_key, &data), Some(command));
+}
+
+#[test]
+fn last_valid_vote_of_every_voter_is_counted() {
+	let mut log = log_with_voters(3);
+	publish(&mut log, &vote(0, 1, 1, true, 3));
+	publish(&mut log, &vote(1, 1, 2, false, 2));
+	// Voter 0 changes its mind
+	publish(&mut log, &vote(0, 2, 1, true, 4));
+	// Voter 2 can not afford 11 votes
+	publish(&mut log, &vote(2, 1, 3, true, 11));
+
+	let tally = process(&log, COORDINATOR_SECRET);
+	assert_eq!((tally.ayes, tally.nays, tally.supporters), (4, 2, 1));
+	assert_eq!(tally.message_root, log.message_root());
+	assert_eq!(tally, process(&log, COORDINATOR_SECRET));
+}
+
+#[test]
+fn key_change_invalidates_commands_signed_with_old_key() {
+	let mut log = log_with_voters(1);
+	let new_key = voter_key(42).public().0;
+	// The voter secretly changes its key and votes nay ...
+	publish(&mut log, &Command::signed(INDEX, 0, 1, new_key, false, 5, &voter_key(1)));
+	// ... before voting aye with its old key, as a briber told it to
+	publish(&mut log, &vote(0, 2, 1, true, 5));
+
+	let tally = process(&log, COORDINATOR_SECRET);
+	assert_eq!((tally.ayes, tally.nays, tally.supporters), (0, 5, 0));
+}
+
+#[test]
+fn invalid_messages_are_skipped() {
+	let mut log = log_with_voters(1);
+	// Replayed nonce, unknown voter, and not a command
+	publish(&mut log, &vote(0, 1, 1, true, 1));
+	publish(&mut log, &vote(0, 1, 1, true, 1));
+	publish(&mut log, &vote(5, 1, 1, true, 9));
+	log.messages.push(([1; 32], vec![0; 3]));
+	// Signed for another referendum
+	let key = voter_key(1);
+	publish(&mut log, &Command::signed(INDEX + 1, 0, 2, key.public().0, false, 9, &key));
+
+	let tally = process(&log, COORDINATOR_SECRET);
+	assert_eq!((tally.ayes, tally.nays, tally.supporters), (1, 0, 1));
+}
+
+#[test]
+fn commitment_depends_on_coordinator_key() {
+	let mut log = log_with_voters(1);
+	publish(&mut log, &vote(0, 1, 1, true, 1));
+
+	// Without the key, no message decrypts, so the tally and the commitment differ
+	let other = process(&log, [8; 32]);
+	assert_eq!((other.ayes, other.supporters), (0, 0));
+	assert_ne!(other.commitment, process(&log, COORDINATOR_SECRET).commitment);
+}
+
+#[test]
+fn tally_commitment_binds_the_message_root_and_voice_credits() {
+	let mut log = log_with_voters(1);
+	publish(&mut log, &vote(0, 1, 1, true, 1));
+	let tally = process(&log, COORDINATOR_SECRET);
+	assert_eq!(
+		tally.tally_commitment,
+		tally_commitment(INDEX, log.message_root(), 100, 1, 0, 1, tally.commitment)
+	);
+
+	// A message that is skipped leaves the tally as it was, but not the message root
+	log.messages.push(([1; 32], vec![0; 3]));
+	let other = process(&log, COORDINATOR_SECRET);
+	assert_eq!((other.ayes, other.nays, other.supporters), (1, 0, 1));
+	assert_ne!(other.tally_commitment, tally.tally_commitment);
+
+	// A smaller budget that the vote still fits in leaves the tally too, but not the commitment
+	log.messages.pop();
+	log.voice_credits = 50;
+	let other = process(&log, COORDINATOR_SECRET);
+	assert_eq!((other.ayes, other.nays, other.supporters), (1, 0, 1));
+	assert_ne!(other.tally_commitment, tally.tally_commitment);
+}
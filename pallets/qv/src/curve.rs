@@ -0,0 +1,75 @@
+//! Curves that price a number of votes.
+
+use frame_support::traits::Get;
+use sp_runtime::traits::{AtLeast32BitUnsigned, CheckedMul};
+use sp_std::marker::PhantomData;
+
+/// Prices votes cast on a referendum of track `Class`.
+///
+/// [`Quadratic`] is the usual choice. A runtime may pick a curve per track by implementing this
+/// trait for a type that dispatches on the class.
+pub trait VoteCostCurve<Balance, Class> {
+	/// The price of `votes` votes on a referendum of track `class`, or `None` if it does not fit
+	/// in a `Balance`.
+	fn cost(votes: u32, class: Class) -> Option<Balance>;
+}
+
+/// The price of `votes` quadratically priced votes, or `None` if it does not fit in a `Balance`.
+pub fn quadratic_cost<Balance: AtLeast32BitUnsigned>(votes: u32) -> Option<Balance> {
+	let votes = Balance::from(votes);
+	votes.checked_mul(&votes)
+}
+
+/// Prices `votes` at `votes`.
+pub struct Linear;
+impl<Balance: AtLeast32BitUnsigned, Class> VoteCostCurve<Balance, Class> for Linear {
+	fn cost(votes: u32, _: Class) -> Option<Balance> {
+		Some(votes.into())
+	}
+}
+
+/// Prices `votes` at `votes^2`.
+pub struct Quadratic;
+impl<Balance: AtLeast32BitUnsigned, Class> VoteCostCurve<Balance, Class> for Quadratic {
+	fn cost(votes: u32, _: Class) -> Option<Balance> {
+		quadratic_cost(votes)
+	}
+}
+
+/// Prices `votes` at `votes^3`.
+pub struct Cubic;
+impl<Balance: AtLeast32BitUnsigned, Class> VoteCostCurve<Balance, Class> for Cubic {
+	fn cost(votes: u32, _: Class) -> Option<Balance> {
+		quadratic_cost::<Balance>(votes)?.checked_mul(&votes.into())
+	}
+}
+
+/// Prices `votes` at `votes^(N / D)`, rounded down, e.g. `votes^1.5` with `N = 3` and `D = 2`.
+///
+/// `votes^N` must fit in a `u128`.
+pub struct RationalPower<N, D>(PhantomData<(N, D)>);
+impl<Balance: AtLeast32BitUnsigned, Class, N: Get<u32>, D: Get<u32>> VoteCostCurve<Balance, Class>
+	for RationalPower<N, D>
+{
+	fn cost(votes: u32, _: Class) -> Option<Balance> {
+		let power = (votes as u128).checked_pow(N::get())?;
+		Balance::try_from(nth_root(power, D::get())).ok()
+	}
+}
+
+/// The largest `r` such that `r^n <= x`. The `0`th root is taken to be `x` itself.
+fn nth_root(x: u128, n: u32) -> u128 {
+	if n <= 1 {
+		return x
+	}
+	// Binary search for the largest `r` in `low..high` with `r^n <= x`
+	let (mut low, mut high) = (0u128, x.min(u64::MAX as u128) + 1);
+	while high - low > 1 {
+		let mid = low + (high - low) / 2;
+		match mid.checked_pow(n) {
+			Some(power) if power <= x => low = mid,
+			_ => high = mid,
+		}
+	}
+	low
+}
@@ -15,22 +15,67 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+mod curve;
+mod membership;
+mod types;
+pub use curve::{quadratic_cost, Cubic, Linear, Quadratic, RationalPower, VoteCostCurve};
+pub use membership::{
+	empty_subtree, merkle_node, MerkleMembership, MerkleProof, MerkleProofOf, VerifyMembership,
+	MAX_MERKLE_DEPTH,
+};
+pub use types::{
+	AccountVote, AccumulateVotes, BackerRecord, BallotCommitment, ClaimState, LaunchTally,
+	MaciPoll, MaciTally, QuadraticTally, RelayedVote, VoiceCreditBudget, VotePricing,
+};
+
 #[frame_support::pallet]
 pub mod pallet {
 	use frame_support::{
 		pallet_prelude::*,
-		traits::{schedule::DispatchTime, Currency, ReservableCurrency},
+		storage::with_storage_layer,
+		traits::{
+			schedule::DispatchTime, Currency, OnUnbalanced, PollStatus, Polling, ReservableCurrency,
+		},
 	};
 	use frame_system::{pallet_prelude::*, RawOrigin};
+	use sp_runtime::{
+		traits::{
+			CheckedSub, Hash, IdentifyAccount, IntegerSquareRoot, SaturatedConversion, Saturating,
+			Verify, Zero,
+		},
+		Perbill,
+	};
+	use sp_std::prelude::*;
 
-	// From pallet_identity we use functions like has_identity() and set_identity()
-	use pallet_identity::IdentityField;
-	const IDENTITY_FIELD_DISPLAY: u64 = IdentityField::Display as u64;
+	// From pallet_identity we use the judgements of registrars
+	use pallet_identity::{Judgement, RegistrarIndex};
+
+	use pallet_referenda::{
+		PalletsOriginOf, ReferendumIndex, ReferendumPhase, TrackIdOf, TracksInfo,
+	};
 
-	use pallet_referenda::ReferendumIndex;
+	use crate::{
+		curve::VoteCostCurve,
+		membership::{merkle_node, VerifyMembership, MAX_MERKLE_DEPTH},
+		types::{
+			AccountVote, AccumulateVotes, BackerRecord, BallotCommitment, ClaimState, LaunchTally,
+			MaciPoll, MaciTally, RelayedVote, VoiceCreditBudget, VotePricing,
+		},
+	};
 
 	type BalanceOf<T> =
 		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	type RelayedVoteOf<T> = RelayedVote<
+		<T as frame_system::Config>::AccountId,
+		<T as frame_system::Config>::BlockNumber,
+	>;
+	type BallotBatchOf<T> = BoundedVec<
+		(RelayedVoteOf<T>, <T as Config>::OffchainSignature),
+		<T as Config>::MaxBallotsPerBatch,
+	>;
+	type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+		<T as frame_system::Config>::AccountId,
+	>>::NegativeImbalance;
 
 	/// Uses tight coupling of pallet_identity and pallet_referenda
 	#[pallet::config]
@@ -39,26 +84,344 @@ pub mod pallet {
 	{
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
-		/// We represent votes by reserving currency
+		/// The registrars whose `Reasonable` or `KnownGood` judgement of an identity makes its
+		/// account eligible to vote.
+		#[pallet::constant]
+		type TrustedRegistrars: Get<Vec<RegistrarIndex>>;
+		/// The origin that generates and deletes the identities that accounts claim with a
+		/// claim code.
+		type IdentityOrigin: EnsureOrigin<Self::Origin>;
+		/// We represent votes by reserving currency, unless they are paid in voice credits
 		type Currency: ReservableCurrency<Self::AccountId>;
-		type LaunchDeposit: Get<u64>;
+		/// Whether votes are paid for by reserving `Currency` or by spending voice credits.
+		#[pallet::constant]
+		type Pricing: Get<VotePricing>;
+		/// The voice credits that every account with an identity is allotted per epoch, when
+		/// votes are paid for in voice credits.
+		#[pallet::constant]
+		type VoiceCreditAllotment: Get<BalanceOf<Self>>;
+		/// The number of blocks in a voice credit epoch. Zero means that the first epoch never
+		/// ends.
+		#[pallet::constant]
+		type EpochLength: Get<Self::BlockNumber>;
+		/// The fraction of an account's unspent voice credits that is carried over into the next
+		/// epoch, on top of its allotment.
+		#[pallet::constant]
+		type CarryOver: Get<Perbill>;
+		/// The most voice credits that are carried over into the next epoch.
+		#[pallet::constant]
+		type MaxCarryOver: Get<BalanceOf<Self>>;
+		/// The number of launch votes that ends a referendum's launch phase.
+		#[pallet::constant]
+		type LaunchDeposit: Get<u32>;
+		/// Prices the launch votes and deciding phase votes cast on a referendum of a track.
+		/// Usually `Quadratic`.
+		type VoteCost: VoteCostCurve<BalanceOf<Self>, TrackIdOf<Self, ()>>;
+		/// The maximum number of votes that can be cast in a single call.
+		#[pallet::constant]
+		type MaxVotesPerCall: Get<u32>;
+		/// The maximum number of backers of a referendum, including its initiator.
+		#[pallet::constant]
+		type MaxBackers: Get<u32>;
+		/// The number of blocks between initiating a referendum and its deadline, unless the
+		/// initiator asks for another lifetime. A referendum that has not been approved by its
+		/// deadline is rejected. The proposal is enacted at the deadline.
+		#[pallet::constant]
+		type ReferendumLifetime: Get<Self::BlockNumber>;
+		/// The shortest lifetime an initiator may ask for.
+		#[pallet::constant]
+		type MinReferendumLifetime: Get<Self::BlockNumber>;
+		/// The longest lifetime an initiator may ask for.
+		#[pallet::constant]
+		type MaxReferendumLifetime: Get<Self::BlockNumber>;
+		/// Whether a proposal whose referendum was rejected, timed out or cancelled may be
+		/// initiated again.
+		#[pallet::constant]
+		type AllowResubmission: Get<bool>;
+		/// The number of blocks after such a referendum ended before its proposal may be
+		/// initiated again.
+		#[pallet::constant]
+		type ResubmissionCooldown: Get<Self::BlockNumber>;
+		/// Whether deciding phase votes are cast as secret ballots, which are committed to first
+		/// and revealed later, rather than counted right away.
+		#[pallet::constant]
+		type CommitReveal: Get<bool>;
+		/// The number of blocks after deciding on a referendum begins during which ballots can be
+		/// committed to.
+		#[pallet::constant]
+		type CommitPeriod: Get<Self::BlockNumber>;
+		/// The number of blocks after the commit period during which committed ballots can be
+		/// revealed.
+		#[pallet::constant]
+		type RevealPeriod: Get<Self::BlockNumber>;
+		/// Whether the reserve behind a ballot that was never revealed is forfeited, rather than
+		/// given back.
+		#[pallet::constant]
+		type ForfeitUnrevealed: Get<bool>;
+		/// Verifies that an anonymous ballot was cast by a member of the voter set. Only a
+		/// zero-knowledge verifier keeps ballots anonymous, `()` turns them off.
+		type Membership: VerifyMembership<Self::Hash>;
+		/// The depth of the voter set's Merkle tree, which holds up to `2^MerkleDepth`
+		/// commitments. At most `MAX_MERKLE_DEPTH`.
+		#[pallet::constant]
+		type MerkleDepth: Get<u32>;
+		/// The origin that has a referendum's deciding phase votes sent as messages encrypted to
+		/// a coordinator, who counts them off chain.
+		type MaciOrigin: EnsureOrigin<Self::Origin>;
+		/// The longest message to a coordinator, in bytes.
+		#[pallet::constant]
+		type MaxMessageLen: Get<u32>;
+		/// The most messages to the coordinator of a referendum.
+		#[pallet::constant]
+		type MaxMessages: Get<u32>;
+		/// The voice credits of every voter in a poll of a coordinator. A voter's votes can not
+		/// cost more than these, at a quadratic price.
+		#[pallet::constant]
+		type MaciVoiceCredits: Get<u32>;
+		/// The signature that voters sign relayed votes with.
+		type OffchainSignature: Verify<Signer = Self::OffchainPublic> + Parameter;
+		/// The public key behind an `OffchainSignature`, which identifies the voter's account.
+		type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
+		/// The priority of relayed votes in the transaction pool.
+		#[pallet::constant]
+		type RelayedVotePriority: Get<TransactionPriority>;
+		/// The most signed ballots in a batch.
+		#[pallet::constant]
+		type MaxBallotsPerBatch: Get<u32>;
+		/// Handler for the unbalanced reduction when slashing launch votes or unrevealed ballots.
+		type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+		/// The tally that deciding phase votes are counted into. Usually `QuadraticTally`.
+		/// Must be the same type as `pallet_referenda::Config::Tally`.
+		type PollTally: AccumulateVotes;
+		/// The size of the electorate that is snapshotted into the tally when deciding on a
+		/// referendum begins. Usually `EligibleVoters`.
+		type Electorate: Get<u32>;
+		/// The referenda that deciding phase votes are cast on. Usually `pallet_referenda`.
+		type Polls: Polling<
+			Self::PollTally,
+			Index = ReferendumIndex,
+			Moment = <Self as frame_system::Config>::BlockNumber,
+		>;
 	}
 
 	#[pallet::pallet]
-	#[pallet::without_storage_info]
 	#[pallet::generate_store(pub(super) trait Store)]
 	pub struct Pallet<T>(_);
 
+	/// The initiator and launch vote backers of a referendum.
+	#[pallet::storage]
+	#[pallet::getter(fn depositors)]
+	pub type Depositors<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ReferendumIndex,
+		Blake2_128Concat,
+		T::AccountId,
+		BackerRecord<BalanceOf<T>>,
+		OptionQuery,
+	>;
+
+	/// The number of backers and launch votes of a referendum, so that they need not be counted.
+	#[pallet::storage]
+	#[pallet::getter(fn launch_totals)]
+	pub type LaunchTotals<T: Config> =
+		StorageMap<_, Blake2_128Concat, ReferendumIndex, LaunchTally, ValueQuery>;
+
+	/// The latest referendum that was initiated for a proposal hash.
+	#[pallet::storage]
+	#[pallet::getter(fn proposal_of)]
+	pub type ProposalOf<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::Hash, ReferendumIndex, OptionQuery>;
+
+	/// The current voice credit epoch.
+	#[pallet::storage]
+	#[pallet::getter(fn current_epoch)]
+	pub type CurrentEpoch<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The voice credits an account had left when it last used them. Accounts that never used
+	/// any have the whole `VoiceCreditAllotment` left.
+	#[pallet::storage]
+	pub type VoiceCredits<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, VoiceCreditBudget<BalanceOf<T>>, OptionQuery>;
+
+	/// The identities generated for claiming, by the hash of their claim code.
+	#[pallet::storage]
+	#[pallet::getter(fn identity_map)]
+	pub type IdentityMap<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::Hash, ClaimState<T::AccountId>, OptionQuery>;
+
+	/// The identity an account claimed, by the hash of its claim code.
+	#[pallet::storage]
+	#[pallet::getter(fn claimed_identity_of)]
+	pub type ClaimedIdentityOf<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, T::Hash, OptionQuery>;
+
+	/// The commitment of an account to the claim code it is going to claim an identity with, and
+	/// the block it was made in.
+	#[pallet::storage]
+	#[pallet::getter(fn claim_commitment_of)]
+	pub type ClaimCommitments<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (T::Hash, T::BlockNumber), OptionQuery>;
+
+	/// The number of claimed identities.
+	#[pallet::storage]
+	#[pallet::getter(fn claimed_identity_count)]
+	pub type ClaimedIdentityCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The accounts counted into the electorate because a trusted registrar verified them, from
+	/// the time they were registered with `register_voter`.
+	#[pallet::storage]
+	#[pallet::getter(fn is_verified_voter)]
+	pub type VerifiedVoters<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	/// The number of accounts in `VerifiedVoters`.
+	#[pallet::storage]
+	#[pallet::getter(fn verified_voter_count)]
+	pub type VerifiedVoterCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The deciding phase vote of an account in a referendum.
+	#[pallet::storage]
+	pub type VotingFor<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ReferendumIndex,
+		Blake2_128Concat,
+		T::AccountId,
+		AccountVote<BalanceOf<T>>,
+		OptionQuery,
+	>;
+
+	/// The secret ballot an account committed to in a referendum, until it is revealed.
+	#[pallet::storage]
+	pub type Commitments<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ReferendumIndex,
+		Blake2_128Concat,
+		T::AccountId,
+		BallotCommitment<T::Hash, BalanceOf<T>>,
+		OptionQuery,
+	>;
+
+	/// The commitment an account registered in the voter set of anonymous ballots.
+	#[pallet::storage]
+	#[pallet::getter(fn anonymous_voter_of)]
+	pub type AnonymousVoterOf<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, T::Hash, OptionQuery>;
+
+	/// The number of commitments in the voter set, which is the position of the next one.
+	#[pallet::storage]
+	#[pallet::getter(fn anonymous_voter_count)]
+	pub type AnonymousVoterCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The rightmost full subtree at each level of the voter set's Merkle tree, from which the
+	/// root is updated when a commitment is added.
+	#[pallet::storage]
+	pub type FilledSubtrees<T: Config> = StorageMap<_, Twox64Concat, u32, T::Hash, ValueQuery>;
+
+	/// The Merkle root of the voter set.
+	#[pallet::storage]
+	#[pallet::getter(fn anonymous_voter_root)]
+	pub type AnonymousVoterRoot<T: Config> = StorageValue<_, T::Hash, ValueQuery>;
+
+	/// The Merkle root of the voter set when deciding on a referendum began. Anonymous ballots on
+	/// the referendum prove membership of this set.
+	#[pallet::storage]
+	#[pallet::getter(fn anonymous_root_of)]
+	pub type AnonymousRootOf<T: Config> =
+		StorageMap<_, Blake2_128Concat, ReferendumIndex, T::Hash, OptionQuery>;
+
+	/// The nullifiers of the anonymous ballots cast on a referendum.
+	#[pallet::storage]
+	pub type Nullifiers<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ReferendumIndex,
+		Blake2_128Concat,
+		T::Hash,
+		(),
+		OptionQuery,
+	>;
+
+	/// The anonymous ballot in a referendum that an account paid for.
+	#[pallet::storage]
+	pub type AnonymousVotes<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ReferendumIndex,
+		Blake2_128Concat,
+		T::AccountId,
+		AccountVote<BalanceOf<T>>,
+		OptionQuery,
+	>;
+
+	/// The referenda whose deciding phase votes are sent as messages to a coordinator.
+	#[pallet::storage]
+	#[pallet::getter(fn maci_poll)]
+	pub type MaciPolls<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		ReferendumIndex,
+		MaciPoll<T::AccountId, T::Hash>,
+		OptionQuery,
+	>;
+
+	/// The state index of an account that signed up to send messages on a referendum.
+	#[pallet::storage]
+	pub type MaciSignups<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ReferendumIndex,
+		Blake2_128Concat,
+		T::AccountId,
+		u32,
+		OptionQuery,
+	>;
+
+	/// The public key that a voter signed up with, by its state index. The coordinator only
+	/// accepts messages signed with it, until a message changes the key.
+	#[pallet::storage]
+	pub type MaciVoterKeys<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ReferendumIndex,
+		Twox64Concat,
+		u32,
+		[u8; 32],
+		OptionQuery,
+	>;
+
+	/// The messages to the coordinator of a referendum, in order: the sender's ephemeral X25519
+	/// public key, and the encrypted command.
+	#[pallet::storage]
+	pub type MaciMessages<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ReferendumIndex,
+		Twox64Concat,
+		u32,
+		([u8; 32], BoundedVec<u8, T::MaxMessageLen>),
+		OptionQuery,
+	>;
+
+	/// The relay nonce that an account's next relayed vote must carry.
 	#[pallet::storage]
-	#[pallet::getter(fn public_props)]
-	pub type Depositors<T: Config> =
-		StorageMap<_, Blake2_128Concat, u32, Vec<(T::AccountId, BalanceOf<T>)>, OptionQuery>;
+	#[pallet::getter(fn relay_nonce)]
+	pub type RelayNonce<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
 
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/v3/runtime/events-and-errors
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
+		/// A referendum was initiated
+		ReferendumInitiated {
+			index: ReferendumIndex,
+			proposal_hash: T::Hash,
+			initiator: T::AccountId,
+		},
+
 		/// An amount from the specified accound was reserved
 		/// Exposing account id here would go against voting anonymity.
 		AmountReserved(BalanceOf<T>),
@@ -66,11 +429,100 @@ pub mod pallet {
 		/// An amount from the specified accound was unreserved
 		AmountUnreserved(BalanceOf<T>),
 
+		/// An amount from the specified accound was slashed
+		AmountSlashed(BalanceOf<T>),
+
 		/// Somebody voted to launch a referendum
 		LaunchVotesCast { number_of_votes: BalanceOf<T>, index: ReferendumIndex },
 
 		/// Referendum launch phase was successfull and will transition to voting phase
 		LaunchPhaseSuccess { index: ReferendumIndex },
+
+		/// Somebody took back some of their launch votes
+		LaunchVotesReduced { number_of_votes: u32, index: ReferendumIndex },
+
+		/// Somebody took back all of their launch votes
+		LaunchVotesWithdrawn { number_of_votes: u32, index: ReferendumIndex },
+
+		/// Somebody voted in the deciding phase of a referendum
+		VotesCast { aye: bool, number_of_votes: u32, index: ReferendumIndex },
+
+		/// Somebody committed to a secret ballot in the deciding phase of a referendum
+		VoteCommitted { index: ReferendumIndex },
+
+		/// A secret ballot that was never revealed was settled, and its reserve forfeited or
+		/// given back
+		UnrevealedSettled { who: T::AccountId, index: ReferendumIndex, forfeited: bool },
+
+		/// A commitment was added to the voter set of anonymous ballots
+		AnonymousVoterRegistered { commitment: T::Hash, root: T::Hash },
+
+		/// A commitment was taken out of the voter set of anonymous ballots, by emptying its leaf
+		AnonymousVoterRevoked { commitment: T::Hash, root: T::Hash },
+
+		/// Somebody cast an anonymous ballot in the deciding phase of a referendum
+		AnonymousVotesCast {
+			aye: bool,
+			number_of_votes: u32,
+			index: ReferendumIndex,
+			nullifier: T::Hash,
+		},
+
+		/// A referendum's deciding phase votes are to be sent as messages to `coordinator`
+		MaciPollOpened { index: ReferendumIndex, coordinator: T::AccountId },
+
+		/// A voter signed up to send messages on a referendum
+		MaciSignedUp { index: ReferendumIndex, state_index: u32, public_key: [u8; 32] },
+
+		/// A message to the coordinator of a referendum was published
+		MaciMessagePublished { index: ReferendumIndex, position: u32 },
+
+		/// The coordinator of a referendum published its tally, which was counted
+		MaciTallyPublished { index: ReferendumIndex, tally: MaciTally<T::Hash> },
+
+		/// A vote was relayed for `voter`, which used up its relay nonce whether or not the vote
+		/// was cast
+		VoteRelayed { voter: T::AccountId, nonce: u32, result: DispatchResult },
+
+		/// A batch of signed ballots on a referendum was submitted, with the result of every
+		/// ballot, in order
+		BallotBatchSubmitted {
+			index: ReferendumIndex,
+			results: Vec<(T::AccountId, DispatchResult)>,
+		},
+
+		/// The votes of an account that is no longer eligible were voided, and refunded
+		VotesVoided { who: T::AccountId, index: ReferendumIndex },
+
+		/// An amount of voice credits was spent on votes
+		VoiceCreditsSpent(BalanceOf<T>),
+
+		/// An amount of voice credits was given back
+		VoiceCreditsRefunded(BalanceOf<T>),
+
+		/// An amount of voice credits was lost to a killed referendum
+		VoiceCreditsBurned(BalanceOf<T>),
+
+		/// A voice credit epoch started, and every identity's budget got refilled
+		EpochStarted { epoch: u32, credits_per_identity: BalanceOf<T> },
+
+		/// An identity was generated to be claimed with the claim code behind `commitment`
+		IdentityGenerated { commitment: T::Hash },
+
+		/// An account committed to the claim code it is going to claim an identity with
+		ClaimCommitted { who: T::AccountId },
+
+		/// An account claimed an identity, which makes it eligible to vote
+		IdentityClaimed { commitment: T::Hash, who: T::AccountId },
+
+		/// An identity was deleted, along with its claim
+		IdentityDeleted { commitment: T::Hash },
+
+		/// An account that a registrar verified joined the electorate
+		VoterRegistered { who: T::AccountId },
+
+		/// An account that is no longer verified by a registrar left the electorate
+		VoterForgotten { who: T::AccountId },
 	}
 
 	// Errors inform users that something went wrong.
@@ -88,6 +540,140 @@ pub mod pallet {
 		AlreadyVoted,
 		/// The referendum is still ongoing
 		StillOngoing,
+		/// The referendum is still in its launch phase, so it can't be voted on yet
+		StillInLaunchPhase,
+		/// The user has not voted on this referendum
+		NotVoted,
+		/// The requested referendum lifetime is outside of the configured bounds
+		LifetimeOutOfBounds,
+		/// The proposal's previous referendum ended too recently for it to be initiated again
+		ResubmissionCoolingDown,
+		/// The referendum already has the maximum number of backers
+		TooManyBackers,
+		/// More votes were cast in a single call than `MaxVotesPerCall` allows
+		TooManyVotes,
+		/// The price of the votes does not fit in a balance
+		VoteCostOverflow,
+		/// The referendum's launch phase is over
+		LaunchPhaseOver,
+		/// The user does not back this referendum with launch votes
+		NotBacker,
+		/// The user has fewer launch votes than it tries to take back, or exactly as many
+		NotEnoughVotes,
+		/// The user has too few voice credits left to pay for the votes
+		InsufficientVoiceCredits,
+		/// The user's identity has not been judged good by a trusted registrar
+		NotVerified,
+		/// No identity was generated for the claim code
+		IdentityNotFound,
+		/// The identity was already generated
+		IdentityAlreadyExists,
+		/// The identity was already claimed
+		IdentityAlreadyClaimed,
+		/// The user already claimed an identity
+		AccountAlreadyClaimed,
+		/// The user is still eligible to vote, so its votes can not be voided
+		StillVerified,
+		/// The user has no votes on this referendum that can be voided
+		NothingToVoid,
+		/// Votes are not cast as secret ballots
+		CommitRevealDisabled,
+		/// Votes can only be cast as secret ballots
+		CommitRevealOnly,
+		/// The referendum's commit period is over, or deciding on it has not begun
+		NotCommitPhase,
+		/// The referendum is not in its reveal period
+		NotRevealPhase,
+		/// The user has not committed to a ballot on this referendum
+		NoCommitment,
+		/// The revealed ballot does not hash to the commitment
+		CommitmentMismatch,
+		/// The revealed ballot has more votes than were committed to
+		RevealExceedsMaximum,
+		/// The referendum's reveal period is not over yet
+		StillRevealing,
+		/// The user already registered a commitment in the voter set
+		AlreadyRegistered,
+		/// The voter set holds as many commitments as its Merkle tree has leaves
+		VoterSetFull,
+		/// Anonymous ballots are paid for in currency, which votes are not priced in
+		NotCurrencyPriced,
+		/// Deciding on the referendum has not begun, so its voter set is not fixed yet
+		NoVoterSet,
+		/// An anonymous ballot with this nullifier was already cast on the referendum
+		NullifierUsed,
+		/// The proof does not show that the ballot comes from a member of the voter set
+		InvalidMembershipProof,
+		/// Votes on the referendum can only be sent as messages to its coordinator
+		MaciPollOnly,
+		/// Votes on the referendum are not sent as messages to a coordinator
+		NotMaciPoll,
+		/// The referendum's votes are already sent as messages to a coordinator
+		MaciPollExists,
+		/// The referendum's commit period, during which messages are sent, is over
+		MessagePeriodOver,
+		/// The user already signed up to send messages on the referendum
+		AlreadySignedUp,
+		/// The referendum has the maximum number of messages
+		TooManyMessages,
+		/// Only the referendum's coordinator can publish its tally
+		NotCoordinator,
+		/// The referendum's tally was already published
+		TallyAlreadyPublished,
+		/// The relayed vote is not signed by its voter
+		BadRelaySignature,
+		/// The relayed vote has expired
+		RelayExpired,
+		/// The relayed vote's nonce was used up already
+		StaleRelayNonce,
+		/// The relayed vote's nonce is ahead of the voter's
+		FutureRelayNonce,
+		/// The ballot is for another referendum than its batch
+		BallotForOtherReferendum,
+		/// The user has not committed to a claim code
+		NoClaimCommitment,
+		/// The claim code can only be revealed in a later block than the commitment to it
+		ClaimRevealTooEarly,
+		/// The claim code and the account do not hash to the commitment
+		ClaimCommitmentMismatch,
+		/// The account is not counted into the electorate through a registrar's judgement
+		NotVerifiedVoter,
+		/// The commit and reveal periods together are longer than the track's decision period
+		BallotPeriodsTooLong,
+		/// The user has not registered a commitment in the voter set
+		NotRegistered,
+		/// The user registered a commitment in the voter set, so it votes anonymously
+		RegisteredAnonymously,
+		/// The account is already counted into the electorate
+		AlreadyVoter,
+		/// The tally could not have been counted from the poll's sign ups and voice credits
+		ImplausibleTally,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Starts a new voice credit epoch every `EpochLength` blocks.
+		///
+		/// Budgets are not refilled here, as there may be any number of identities. Each one is
+		/// refilled the next time its account uses voice credits.
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let length = T::EpochLength::get();
+			if T::Pricing::get() != VotePricing::VoiceCredits ||
+				length.is_zero() || !(now % length).is_zero()
+			{
+				return 0
+			}
+
+			let epoch = <CurrentEpoch<T>>::mutate(|epoch| {
+				*epoch = epoch.saturating_add(1);
+				*epoch
+			});
+			Self::deposit_event(Event::EpochStarted {
+				epoch,
+				credits_per_identity: T::VoiceCreditAllotment::get(),
+			});
+			T::DbWeight::get().reads_writes(1, 1)
+		}
 	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -100,57 +686,91 @@ pub mod pallet {
 		///
 		/// - `origin`: must be `Signed` and the account must have funds available for the
 		///   referendum's track's Decision Deposit.
+		/// - `proposal_origin`: The origin from which the proposal should be executed. It selects
+		///   the referendum's track, and with it the periods and the Decision Deposit.
 		/// - `proposal`: A simple hash for now.
+		/// - `lifetime`: The number of blocks until the referendum's deadline. `None` means
+		///   `ReferendumLifetime`. Must be within `MinReferendumLifetime..=MaxReferendumLifetime`.
 		///
-		/// This function emits an event Submitted that contains the
-		///  1. index,
-		///  2. the track,
-		///  3. and the hash
-		/// of the referendum.
-		/// We therefore make the assumption in the rest of the code that a mapping
-		///
-		/// f(proposal) -> index,
+		/// With `CommitReveal`, the commit and reveal periods must fit in the decision period of
+		/// the track.
 		///
-		/// exists off-chain, and all other functions later in the referendum flow
-		/// uses the index to refer to the referendum concering the proposal
+		/// The index that pallet_referenda assigns to the referendum is emitted in
+		/// `ReferendumInitiated`, together with the hash and the initiator.
+		/// All other functions later in the referendum flow use the index to refer to the
+		/// referendum concerning the proposal.
 		///
 		/// Reserving the submission deposit is handled entirely inside pallet_referenda.
 		/// Refunding it should also be handled inside pallet_referenda.
 		///
-		/// Emits `pallet_referenda::Event::Submitted`.
+		/// Emits `ReferendumInitiated` and `pallet_referenda::Event::Submitted`.
 		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
-		pub fn initiate_referendum(origin: OriginFor<T>, proposal: T::Hash) -> DispatchResult {
-			const REFERENDUM_BLOCKS_TOTAL: u32 = 892800; // =  2*31*24*60*60/6 = "Two months" / "block time"
-			let now = <frame_system::Pallet<T>>::block_number();
+		pub fn initiate_referendum(
+			origin: OriginFor<T>,
+			proposal_origin: Box<PalletsOriginOf<T>>,
+			proposal: T::Hash,
+			lifetime: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			// Referenda are only submitted through this pallet, so `SubmitOrigin` is not checked:
+			// the runtime should make it an origin that no extrinsic has
+			let who = ensure_signed(origin)?;
+			// pallet_referenda does not check that the track it finds for an origin exists
+			let track = T::Tracks::track_for(&proposal_origin)
+				.ok()
+				.and_then(T::Tracks::info)
+				.ok_or(Error::<T>::NoTrack)?;
+			// Secret ballots must be revealed before deciding on the referendum times out
+			if T::CommitReveal::get() &&
+				T::CommitPeriod::get().saturating_add(T::RevealPeriod::get()) >
+					track.decision_period
+			{
+				return Err(Error::<T>::BallotPeriodsTooLong.into())
+			}
+			let lifetime = lifetime.unwrap_or_else(T::ReferendumLifetime::get);
+			if lifetime < T::MinReferendumLifetime::get() ||
+				lifetime > T::MaxReferendumLifetime::get()
+			{
+				return Err(Error::<T>::LifetimeOutOfBounds.into())
+			}
+			if let Some(previous) = <ProposalOf<T>>::get(proposal) {
+				Self::ensure_resubmittable(previous)?;
+			}
 
-			let res = <pallet_referenda::Pallet<T>>::submit(
-				origin.clone(),
-				Box::new(RawOrigin::Root.into()),
+			let deadline = <frame_system::Pallet<T>>::block_number().saturating_add(lifetime);
+			let index = <pallet_referenda::Pallet<T>>::do_submit(
+				who.clone(),
+				*proposal_origin,
 				proposal,
-				DispatchTime::At(now + REFERENDUM_BLOCKS_TOTAL.into()),
-			);
+				DispatchTime::At(deadline),
+				Some(deadline),
+			)?;
 
-			if res == Ok(()) {
-				// Getting index like this is really fragile in case pallet-referenda changes its
-				// indexing scheme The problem is pallet-referenda keeps the members of the
-				// ReferendumStatus struct private. Some workaround for that must be found
-				let index: ReferendumIndex =
-					pallet_referenda::pallet::ReferendumCount::<T>::get() - 1;
-				let who = ensure_signed(origin)?;
-				let backer_element: (T::AccountId, BalanceOf<T>) = (who, 0u32.into());
-				<Depositors<T>>::append(index, backer_element);
-			}
-			res
+			Self::set_backer(
+				index,
+				&who,
+				BackerRecord { votes: 0, cost: Zero::zero(), decision_deposit: false },
+			)?;
+			<ProposalOf<T>>::insert(proposal, index);
+			Self::deposit_event(Event::ReferendumInitiated {
+				index,
+				proposal_hash: proposal,
+				initiator: who,
+			});
+			Ok(())
 		}
 
 		/// Cast launch votes for a referendum that is in the launch phase.
 		///
-		/// - `origin`: must be `Signed` and the account must have funds equal to or larger than
+		/// - `origin`: must be `Signed` by an account that is eligible to vote, see
+		///   `ensure_eligible`, and the account must have funds equal to or larger than
 		///   number_of_votes^2
 		/// - `number_of_votes`: The origin wants to cast this number of quadratically priced votes
 		/// - `index`: The index of the submitted referendum whose Decision Deposit is yet to be
 		///   posted.
 		///
+		/// A backer that casts launch votes again tops up its earlier votes, and only pays the
+		/// marginal cost: going from 3 to 5 votes costs 5^2 - 3^2 = 16.
+		///
 		/// This splitting of the deposits across several origins, and the quadratic pricing,
 		/// are not implemented inside pallet-referenda.
 		/// Therefore we implement this book-keeping ourselves.s
@@ -167,47 +787,49 @@ pub mod pallet {
 				// This zero-check could probably have been done with a trait
 				return Err(Error::<T>::ZeroVote.into())
 			}
+			Self::ensure_votes_per_call(number_of_votes)?;
 
 			let who = ensure_signed(origin.clone())?;
+			Self::ensure_eligible(&who)?;
 			<pallet_referenda::Pallet<T>>::ensure_ongoing(index)?;
 
-			let depositors_vec = <Depositors<T>>::get(index).unwrap_or_default();
-			let disallowed_voters: Vec<T::AccountId> =
-				depositors_vec.iter().map(|x| x.0.clone()).collect();
-
-			let disallowed = disallowed_voters.iter().any(|x| *x == who);
-			if disallowed {
-				return Err(Error::<T>::AlreadyVoted.into())
-			}
-
-			let number_of_votes_already =
-				depositors_vec.iter().fold(0u32.into(), |acc: BalanceOf<T>, x| acc + x.1);
+			let (votes_before, cost_before) = match <Depositors<T>>::get(index, &who) {
+				// The initiator backs the referendum with its submission deposit
+				Some(backer) if backer.votes == 0 => return Err(Error::<T>::AlreadyVoted.into()),
+				Some(backer) => (backer.votes, backer.cost),
+				None => (0, Zero::zero()),
+			};
+			let votes = votes_before.saturating_add(number_of_votes);
+			let cost = Self::cost_of(votes, index)?;
 
 			// Is the aggregated deposit large enough yet?
-			if number_of_votes_already + number_of_votes.into() >=
-				(T::LaunchDeposit::get() as u32).into()
-			{
-				// Last depositor should get refunded through pallet_referenda
-				// This must simply be remembered later, when writing
-				// the refund_launch_deposit function
+			let number_of_votes_already = <LaunchTotals<T>>::get(index).votes;
+			let decision_deposit =
+				number_of_votes_already.saturating_add(number_of_votes) >= T::LaunchDeposit::get();
+			Self::set_backer(index, &who, BackerRecord { votes, cost, decision_deposit })?;
+			if decision_deposit {
+				// Last depositor's whole cost is held by pallet_referenda as the Decision Deposit,
+				// and gets refunded through pallet_referenda. Voice credits are not a currency
+				// pallet_referenda can hold, so they are spent here and the deposit is empty.
+				let deposit = match T::Pricing::get() {
+					VotePricing::Currency => {
+						Self::release(&who, cost_before);
+						cost
+					},
+					VotePricing::VoiceCredits => {
+						Self::reserve_an_amount_of_token(
+							origin.clone(),
+							cost.saturating_sub(cost_before),
+						)?;
+						Zero::zero()
+					},
+				};
 				<pallet_referenda::Pallet<T>>::place_triggering_decision_deposit(
-					origin.clone(),
-					index,
-					(number_of_votes * number_of_votes).into(),
-				)?;
-				let backer_element: (<T as frame_system::Config>::AccountId, BalanceOf<T>) =
-					(who.clone(), number_of_votes.into());
-				<Depositors<T>>::append(index, backer_element);
-				Ok(())
+					origin, index, deposit,
+				)
 			} else {
 				// Register the deposit
-				Self::reserve_an_amount_of_token(
-					origin.clone(),
-					(number_of_votes * number_of_votes).into(),
-				)?;
-				let backer_element: (<T as frame_system::Config>::AccountId, BalanceOf<T>) =
-					(who.clone(), number_of_votes.into());
-				<Depositors<T>>::append(index, backer_element);
+				Self::reserve_an_amount_of_token(origin, cost.saturating_sub(cost_before))?;
 				Self::deposit_event(Event::LaunchVotesCast {
 					number_of_votes: number_of_votes.into(),
 					index,
@@ -215,79 +837,1594 @@ pub mod pallet {
 				Ok(())
 			}
 		}
-	}
 
-	/// Helper functions
-	impl<T: Config> Pallet<T> {
-		/// Reserves an amount of token for a user.
-		pub fn reserve_an_amount_of_token(
+		/// Take back some of one's launch votes for a referendum that is in the launch phase.
+		///
+		/// - `origin`: must be `Signed` and must back the referendum with more than
+		///   `number_of_votes` launch votes.
+		/// - `number_of_votes`: The number of launch votes to take back.
+		/// - `index`: The index of the referendum.
+		///
+		/// The difference in quadratic cost is unreserved: going from 5 to 3 votes unreserves
+		/// 5^2 - 3^2 = 16. Not possible once the launch phase is over.
+		///
+		/// Emits `LaunchVotesReduced`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn reduce_launch_votes(
 			origin: OriginFor<T>,
-			amount: BalanceOf<T>,
+			number_of_votes: u32,
+			index: ReferendumIndex,
 		) -> DispatchResult {
+			if number_of_votes == 0u32 {
+				return Err(Error::<T>::ZeroVote.into())
+			}
+
 			let who = ensure_signed(origin)?;
-			if pallet_identity::Pallet::<T>::has_identity(&who, IDENTITY_FIELD_DISPLAY) {
-				// If funds are too low and Err will be returned
-				let res = <T as Config>::Currency::reserve(&who, amount);
-				if res == Ok(()) {
-					Self::deposit_event(Event::AmountReserved(amount));
-				}
-				res
-			} else {
-				Err(Error::<T>::NoIdentity.into())
+			let status = <pallet_referenda::Pallet<T>>::ensure_ongoing(index)?;
+			if status.decision_deposit.is_some() {
+				return Err(Error::<T>::LaunchPhaseOver.into())
 			}
+			let backer = <Depositors<T>>::get(index, &who).ok_or(Error::<T>::NotBacker)?;
+			if number_of_votes >= backer.votes {
+				return Err(Error::<T>::NotEnoughVotes.into())
+			}
+
+			let votes = backer.votes - number_of_votes;
+			let cost = Self::cost_of(votes, index)?;
+			let refund = backer.cost.saturating_sub(cost);
+			Self::set_backer(index, &who, BackerRecord { votes, cost, decision_deposit: false })?;
+			Self::release(&who, refund);
+			Self::deposit_event(Event::LaunchVotesReduced { number_of_votes, index });
+			Ok(())
 		}
 
-		/// Unreserves an amount of token for a user.
-		pub fn unreserve_an_amount_of_token(
+		/// Take back all of one's launch votes for a referendum that is in the launch phase.
+		///
+		/// - `origin`: must be `Signed` and must back the referendum with launch votes.
+		/// - `index`: The index of the referendum.
+		///
+		/// The whole quadratic cost of the launch votes is unreserved, and the origin no longer
+		/// backs the referendum. Not possible once the launch phase is over.
+		///
+		/// Emits `LaunchVotesWithdrawn`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn withdraw_launch_votes(
+			origin: OriginFor<T>,
+			index: ReferendumIndex,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let status = <pallet_referenda::Pallet<T>>::ensure_ongoing(index)?;
+			if status.decision_deposit.is_some() {
+				return Err(Error::<T>::LaunchPhaseOver.into())
+			}
+			let backer = match <Depositors<T>>::get(index, &who) {
+				// The initiator backs the referendum with its submission deposit
+				Some(backer) if backer.votes > 0 => backer,
+				_ => return Err(Error::<T>::NotBacker.into()),
+			};
+
+			Self::remove_backer(index, &who);
+			Self::release(&who, backer.cost);
+			Self::deposit_event(Event::LaunchVotesWithdrawn {
+				number_of_votes: backer.votes,
+				index,
+			});
+			Ok(())
+		}
+
+		/// Void the votes of an account that is no longer eligible to vote, for example because
+		/// it cleared its identity, on a referendum that is still ongoing.
+		///
+		/// - `origin`: must be `Signed`, by any account.
+		/// - `who`: The account whose votes are voided. `ensure_verified` must fail for it.
+		/// - `index`: The index of the referendum.
+		///
+		/// The account's deciding phase vote is taken out of the tally, its unrevealed secret
+		/// ballot is dropped, and its launch votes no longer back the referendum, unless they were
+		/// placed as the Decision Deposit. What it paid for the voided votes is given back.
+		///
+		/// Emits `VotesVoided`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 4))]
+		pub fn void_votes(
 			origin: OriginFor<T>,
 			who: T::AccountId,
-			amount: BalanceOf<T>,
+			index: ReferendumIndex,
 		) -> DispatchResult {
-			ensure_root(origin)?;
-			if pallet_identity::Pallet::<T>::has_identity(&who, IDENTITY_FIELD_DISPLAY) {
-				<T as Config>::Currency::unreserve(&who, amount);
-				Self::deposit_event(Event::AmountUnreserved(amount));
-				Ok(())
-			} else {
-				Err(Error::<T>::NoIdentity.into())
+			ensure_signed(origin)?;
+			if Self::ensure_verified(&who).is_ok() {
+				return Err(Error::<T>::StillVerified.into())
+			}
+			<pallet_referenda::Pallet<T>>::ensure_ongoing(index)?;
+
+			let mut voided = false;
+			if let Some(backer) = <Depositors<T>>::get(index, &who) {
+				// The initiator's submission deposit and the Decision Deposit are not votes
+				// that this pallet holds
+				if backer.votes > 0 && !backer.decision_deposit {
+					Self::remove_backer(index, &who);
+					Self::release(&who, backer.cost);
+					voided = true;
+				}
+			}
+			if let Some(ballot) = <Commitments<T>>::take(index, &who) {
+				Self::release(&who, ballot.cost);
+				voided = true;
+			}
+			if let Some(vote) = <VotingFor<T>>::take(index, &who) {
+				T::Polls::try_access_poll(index, |poll_status| match poll_status {
+					PollStatus::Ongoing(tally, _) => {
+						if vote.aye {
+							tally.remove_aye(vote.votes);
+						} else {
+							tally.remove_nay(vote.votes);
+						}
+						Ok(())
+					},
+					_ => Err(pallet_referenda::Error::<T>::NotOngoing.into()),
+				})?;
+				Self::release(&who, vote.cost);
+				voided = true;
 			}
+			if !voided {
+				return Err(Error::<T>::NothingToVoid.into())
+			}
+
+			Self::deposit_event(Event::VotesVoided { who, index });
+			Ok(())
 		}
 
-		/// Refund all Decision Deposits (launch votes) for a referendum.
+		/// Generate an identity that can be claimed once, by whoever knows its claim code.
 		///
-		/// - `origin`: must be `Root`
-		/// - `index`: The index of the submitted referendum whose Decision Deposit is yet to be
-		///   posted.
+		/// - `origin`: must be `IdentityOrigin`.
+		/// - `commitment`: The hash of the secret claim code, which is handed to the citizen off
+		///   chain.
 		///
-		/// This function should be triggered by the state machine inside pallet_referendum
-		/// (the service_referendum() function).
+		/// Emits `IdentityGenerated`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn create_identity(origin: OriginFor<T>, commitment: T::Hash) -> DispatchResult {
+			T::IdentityOrigin::ensure_origin(origin)?;
+			if <IdentityMap<T>>::contains_key(commitment) {
+				return Err(Error::<T>::IdentityAlreadyExists.into())
+			}
+
+			<IdentityMap<T>>::insert(commitment, ClaimState::Unclaimed);
+			Self::deposit_event(Event::IdentityGenerated { commitment });
+			Ok(())
+		}
+
+		/// Commit to the claim code that the account is going to claim an identity with, without
+		/// revealing it.
 		///
-		/// The splitting of the deposits across several origins, and the quadratic pricing,
-		/// are not implemented inside pallet-referenda.
-		/// Therefore we implement this book-keeping ourselves inside pallet-qv.
-		/// We build on top of
-		/// Referenda::refund_decision_deposit()
-		/// and want to stay as close to its behaviour as we can.
-		pub fn refund_launch_votes(origin: OriginFor<T>, index: ReferendumIndex) -> DispatchResult {
-			ensure_root(origin.clone())?;
-			if <pallet_referenda::Pallet<T>>::is_ongoing(index) {
-				return Err(Error::<T>::StillOngoing.into())
+		/// - `origin`: must be `Signed` by an account that has not claimed an identity yet.
+		/// - `commitment`: The hash of the claim code and the account, `hash_of(&(code, who))`.
+		///
+		/// Committing again replaces the earlier commitment.
+		///
+		/// Emits `ClaimCommitted`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn commit_claim(origin: OriginFor<T>, commitment: T::Hash) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			if <ClaimedIdentityOf<T>>::contains_key(&who) {
+				return Err(Error::<T>::AccountAlreadyClaimed.into())
+			}
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			<ClaimCommitments<T>>::insert(&who, (commitment, now));
+			Self::deposit_event(Event::ClaimCommitted { who });
+			Ok(())
+		}
+
+		/// Claim the identity generated for a claim code, which makes the account eligible to
+		/// vote.
+		///
+		/// - `origin`: must be `Signed` by an account that has not claimed an identity yet, and
+		///   that committed to `code` with `commit_claim` in an earlier block.
+		/// - `code`: The secret claim code.
+		///
+		/// The code is public once this call is submitted, but whoever copies it can not claim
+		/// the identity with it: the commitment binds it to the account that made it, before the
+		/// code was revealed.
+		///
+		/// Emits `IdentityClaimed`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn claim_identity(origin: OriginFor<T>, code: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			if <ClaimedIdentityOf<T>>::contains_key(&who) {
+				return Err(Error::<T>::AccountAlreadyClaimed.into())
+			}
+			let (claim, committed_at) =
+				<ClaimCommitments<T>>::get(&who).ok_or(Error::<T>::NoClaimCommitment)?;
+			if committed_at >= <frame_system::Pallet<T>>::block_number() {
+				return Err(Error::<T>::ClaimRevealTooEarly.into())
+			}
+			if T::Hashing::hash_of(&(code, &who)) != claim {
+				return Err(Error::<T>::ClaimCommitmentMismatch.into())
+			}
+			let commitment = T::Hashing::hash(&code);
+			match <IdentityMap<T>>::get(commitment) {
+				Some(ClaimState::Unclaimed) => (),
+				Some(ClaimState::Claimed(_)) =>
+					return Err(Error::<T>::IdentityAlreadyClaimed.into()),
+				None => return Err(Error::<T>::IdentityNotFound.into()),
+			}
+
+			<ClaimCommitments<T>>::remove(&who);
+			// The account is counted through its claimed identity from now on
+			Self::forget_verified_voter(&who);
+
+			<IdentityMap<T>>::insert(commitment, ClaimState::Claimed(who.clone()));
+			<ClaimedIdentityOf<T>>::insert(&who, commitment);
+			<ClaimedIdentityCount<T>>::mutate(|count| *count = count.saturating_add(1));
+			Self::deposit_event(Event::IdentityClaimed { commitment, who });
+			Ok(())
+		}
+
+		/// Delete an identity, claimed or not. An account that claimed it is no longer
+		/// eligible to vote through it.
+		///
+		/// - `origin`: must be `IdentityOrigin`.
+		/// - `commitment`: The hash of the identity's claim code.
+		///
+		/// Emits `IdentityDeleted`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 2))]
+		pub fn delete_identity(origin: OriginFor<T>, commitment: T::Hash) -> DispatchResult {
+			T::IdentityOrigin::ensure_origin(origin)?;
+			let state = <IdentityMap<T>>::take(commitment).ok_or(Error::<T>::IdentityNotFound)?;
+			if let ClaimState::Claimed(who) = state {
+				<ClaimedIdentityOf<T>>::remove(who);
+				<ClaimedIdentityCount<T>>::mutate(|count| *count = count.saturating_sub(1));
+			}
+
+			Self::deposit_event(Event::IdentityDeleted { commitment });
+			Ok(())
+		}
+
+		/// Count an account that a trusted registrar verified into the electorate, so that it
+		/// can vote.
+		///
+		/// pallet_identity keeps no count of judgements, so accounts are counted as they are
+		/// registered, rather than when they first vote, which can be after the electorate of a
+		/// referendum was snapshotted.
+		///
+		/// - `origin`: must be `Signed`, by any account.
+		/// - `who`: The account, which `ensure_verified` must accept, and which must not have
+		///   claimed an identity, as claimed identities are counted already.
+		///
+		/// Emits `VoterRegistered`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 2))]
+		pub fn register_voter(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			ensure_signed(origin)?;
+			Self::ensure_verified(&who)?;
+			if <ClaimedIdentityOf<T>>::contains_key(&who) ||
+				<VerifiedVoters<T>>::contains_key(&who)
+			{
+				return Err(Error::<T>::AlreadyVoter.into())
 			}
 
-			let mut depositors_vec = <Depositors<T>>::get(index).unwrap_or_default();
-			if !depositors_vec.is_empty() {
-				depositors_vec.pop(); // Last voter is refunded by pallet_referenda
-				depositors_vec
-					.iter()
-					.next() // first backer is the initiator, which is handled by pallet_referenda
-					.map(|(id, amount)| {
-						Self::unreserve_an_amount_of_token(origin.clone(), (*id).clone(), *amount)
-					});
+			<VerifiedVoters<T>>::insert(&who, ());
+			<VerifiedVoterCount<T>>::mutate(|count| *count = count.saturating_add(1));
+			Self::deposit_event(Event::VoterRegistered { who });
+			Ok(())
+		}
 
-				<pallet_referenda::Pallet<T>>::refund_decision_deposit(origin.clone(), index)?;
+		/// Take an account that no registrar verifies anymore out of the electorate.
+		///
+		/// - `origin`: must be `Signed`, by any account.
+		/// - `who`: The account, which must be in `VerifiedVoters`. `ensure_verified` must fail
+		///   for it.
+		///
+		/// Emits `VoterForgotten`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 2))]
+		pub fn forget_voter(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			ensure_signed(origin)?;
+			if !<VerifiedVoters<T>>::contains_key(&who) {
+				return Err(Error::<T>::NotVerifiedVoter.into())
+			}
+			if Self::ensure_verified(&who).is_ok() {
+				return Err(Error::<T>::StillVerified.into())
 			}
 
+			Self::forget_verified_voter(&who);
+			Self::deposit_event(Event::VoterForgotten { who });
 			Ok(())
 		}
+
+		/// Cast quadratically priced votes in favour of a referendum that has left its launch
+		/// phase.
+		///
+		/// - `origin`: must be `Signed` and the account must have funds equal to or larger than
+		///   number_of_votes^2
+		/// - `number_of_votes`: The origin wants to cast this number of quadratically priced votes
+		/// - `index`: The index of the referendum whose Decision Deposit has been posted, and
+		///   that deciding on has begun.
+		///
+		/// Every account can vote once per referendum, and must put all its votes on either aye
+		/// or nay. An account that registered a commitment in the voter set votes anonymously
+		/// instead.
+		///
+		/// Emits `VotesCast`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn vote_aye(
+			origin: OriginFor<T>,
+			number_of_votes: u32,
+			index: ReferendumIndex,
+		) -> DispatchResult {
+			Self::do_vote(origin, true, number_of_votes, index)
+		}
+
+		/// Cast quadratically priced votes against a referendum that has left its launch phase.
+		///
+		/// See `vote_aye`.
+		///
+		/// Emits `VotesCast`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn vote_nay(
+			origin: OriginFor<T>,
+			number_of_votes: u32,
+			index: ReferendumIndex,
+		) -> DispatchResult {
+			Self::do_vote(origin, false, number_of_votes, index)
+		}
+
+		/// Commit to a secret ballot on a referendum whose commit period is running.
+		///
+		/// - `origin`: must be `Signed` and the account must have funds equal to or larger than
+		///   the price of `max_votes`.
+		/// - `index`: The index of the referendum, which must be deciding.
+		/// - `commitment`: The hash of `(who, index, aye, votes, salt)`, where `who` is the
+		///   voter's account and `salt` is 32 secret bytes. Binding the account and the referendum
+		///   keeps others from copying the commitment, and revealing the same ballot.
+		/// - `max_votes`: The most votes the ballot may reveal. Their price is reserved now, so the
+		///   reserve does not tell how many votes the ballot has.
+		///
+		/// The commit period starts when deciding on the referendum begins, and lasts
+		/// `CommitPeriod` blocks. The ballot is only counted once it is revealed with
+		/// `reveal_vote`, so the tally gives nothing away while ballots are being committed to.
+		///
+		/// Emits `VoteCommitted`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn commit_vote(
+			origin: OriginFor<T>,
+			index: ReferendumIndex,
+			commitment: T::Hash,
+			max_votes: u32,
+		) -> DispatchResult {
+			if !T::CommitReveal::get() {
+				return Err(Error::<T>::CommitRevealDisabled.into())
+			}
+			if max_votes == 0u32 {
+				return Err(Error::<T>::ZeroVote.into())
+			}
+			Self::ensure_votes_per_call(max_votes)?;
+
+			let who = ensure_signed(origin.clone())?;
+			Self::ensure_not_maci(index)?;
+			let (commit_end, _) = Self::ballot_periods(index)?;
+			if <frame_system::Pallet<T>>::block_number() >= commit_end {
+				return Err(Error::<T>::NotCommitPhase.into())
+			}
+			if <VotingFor<T>>::contains_key(index, &who) ||
+				<Commitments<T>>::contains_key(index, &who)
+			{
+				return Err(Error::<T>::AlreadyVoted.into())
+			}
+
+			let cost = Self::cost_of(max_votes, index)?;
+			Self::reserve_an_amount_of_token(origin, cost)?;
+			<Commitments<T>>::insert(index, &who, BallotCommitment { commitment, max_votes, cost });
+			Self::deposit_event(Event::VoteCommitted { index });
+			Ok(())
+		}
+
+		/// Reveal a secret ballot during the referendum's reveal period, which counts its votes.
+		///
+		/// - `origin`: must be `Signed` by the account that committed to the ballot.
+		/// - `index`: The index of the referendum.
+		/// - `aye`, `votes`, `salt`: The ballot, which must hash to the commitment. `votes` must
+		///   not exceed the committed `max_votes`.
+		///
+		/// The reveal period follows the commit period and lasts `RevealPeriod` blocks. What was
+		/// reserved beyond the price of `votes` is given back.
+		///
+		/// Emits `VotesCast`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 3))]
+		pub fn reveal_vote(
+			origin: OriginFor<T>,
+			index: ReferendumIndex,
+			aye: bool,
+			votes: u32,
+			salt: [u8; 32],
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (commit_end, reveal_end) = Self::ballot_periods(index)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+			if now < commit_end || now >= reveal_end {
+				return Err(Error::<T>::NotRevealPhase.into())
+			}
+			let ballot = <Commitments<T>>::get(index, &who).ok_or(Error::<T>::NoCommitment)?;
+			if T::Hashing::hash_of(&(&who, index, aye, votes, salt)) != ballot.commitment {
+				return Err(Error::<T>::CommitmentMismatch.into())
+			}
+			if votes == 0u32 {
+				return Err(Error::<T>::ZeroVote.into())
+			}
+			if votes > ballot.max_votes {
+				return Err(Error::<T>::RevealExceedsMaximum.into())
+			}
+
+			let cost = Self::cost_of(votes, index)?;
+			Self::count_votes(index, aye, votes)?;
+
+			<Commitments<T>>::remove(index, &who);
+			Self::release(&who, ballot.cost.saturating_sub(cost));
+			<VotingFor<T>>::insert(index, &who, AccountVote { aye, votes, cost });
+			Self::deposit_event(Event::VotesCast { aye, number_of_votes: votes, index });
+			Ok(())
+		}
+
+		/// Settle a secret ballot that was not revealed in time.
+		///
+		/// - `origin`: must be `Signed`, by any account.
+		/// - `who`: The account that committed to the ballot.
+		/// - `index`: The index of a referendum whose reveal period is over, or that is no longer
+		///   ongoing.
+		///
+		/// The reserve behind the ballot is forfeited if `ForfeitUnrevealed` is set, and given
+		/// back otherwise.
+		///
+		/// Emits `UnrevealedSettled`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn settle_unrevealed(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			index: ReferendumIndex,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			if <pallet_referenda::Pallet<T>>::is_ongoing(index) {
+				let (_, reveal_end) = Self::ballot_periods(index)?;
+				if <frame_system::Pallet<T>>::block_number() < reveal_end {
+					return Err(Error::<T>::StillRevealing.into())
+				}
+			}
+			let ballot = <Commitments<T>>::take(index, &who).ok_or(Error::<T>::NoCommitment)?;
+
+			let forfeited = T::ForfeitUnrevealed::get();
+			if forfeited {
+				Self::forfeit(&who, ballot.cost);
+			} else {
+				Self::release(&who, ballot.cost);
+			}
+			Self::deposit_event(Event::UnrevealedSettled { who, index, forfeited });
+			Ok(())
+		}
+
+		/// Register a commitment in the voter set, so that the account can later cast anonymous
+		/// ballots from any other account.
+		///
+		/// - `origin`: must be `Signed` by an account that is eligible to vote, see
+		///   `ensure_eligible`, and has not registered a commitment yet.
+		/// - `commitment`: The hash of a key whose secret part stays with the voter, which the
+		///   anonymous ballots prove to be behind a commitment in the set.
+		///
+		/// Anonymous ballots on a referendum can only come from commitments that were registered
+		/// before deciding on it began. An account that registered a commitment casts its
+		/// deciding phase votes anonymously only, until the commitment is revoked with
+		/// `revoke_anonymous_voter`. It can be replaced with `replace_anonymous_voter`.
+		///
+		/// Emits `AnonymousVoterRegistered`.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(3, 3 + T::MerkleDepth::get() as u64)
+		)]
+		pub fn register_anonymous_voter(
+			origin: OriginFor<T>,
+			commitment: T::Hash,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_eligible(&who)?;
+			if <AnonymousVoterOf<T>>::contains_key(&who) {
+				return Err(Error::<T>::AlreadyRegistered.into())
+			}
+
+			let root = Self::insert_commitment(commitment)?;
+			<AnonymousVoterOf<T>>::insert(&who, commitment);
+			Self::deposit_event(Event::AnonymousVoterRegistered { commitment, root });
+			Ok(())
+		}
+
+		/// Replace the commitment that the account registered in the voter set, for example
+		/// because the key behind it was exposed.
+		///
+		/// - `origin`: must be `Signed` by an account that is eligible to vote, see
+		///   `ensure_eligible`, and that registered a commitment.
+		/// - `commitment`: The new commitment.
+		/// - `leaf_index`: The position of the old commitment among the leaves of the voter set.
+		/// - `path`: The siblings of the nodes on the way from the old commitment up to the
+		///   current root of the voter set.
+		///
+		/// The old commitment's leaf is emptied and the new commitment is added as the next leaf,
+		/// so only the new one is in the voter set of referenda that deciding begins on from now.
+		///
+		/// Emits `AnonymousVoterRevoked` and `AnonymousVoterRegistered`.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(4, 4 + 2 * T::MerkleDepth::get() as u64)
+		)]
+		pub fn replace_anonymous_voter(
+			origin: OriginFor<T>,
+			commitment: T::Hash,
+			leaf_index: u32,
+			path: BoundedVec<T::Hash, ConstU32<MAX_MERKLE_DEPTH>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_eligible(&who)?;
+			let old = <AnonymousVoterOf<T>>::get(&who).ok_or(Error::<T>::NotRegistered)?;
+
+			let root = Self::remove_commitment(old, leaf_index, &path)?;
+			Self::deposit_event(Event::AnonymousVoterRevoked { commitment: old, root });
+			let root = Self::insert_commitment(commitment)?;
+			<AnonymousVoterOf<T>>::insert(&who, commitment);
+			Self::deposit_event(Event::AnonymousVoterRegistered { commitment, root });
+			Ok(())
+		}
+
+		/// Take the commitment of an account that is no longer eligible to vote, for example
+		/// because its identity was cleared or deleted, out of the voter set.
+		///
+		/// - `origin`: must be `Signed`, by any account.
+		/// - `who`: The account that registered the commitment. `ensure_verified` must fail for
+		///   it.
+		/// - `leaf_index`: The position of the commitment among the leaves of the voter set.
+		/// - `path`: The siblings of the nodes on the way from the commitment up to the current
+		///   root of the voter set.
+		///
+		/// The commitment's leaf is emptied, so it is not in the voter set of referenda that
+		/// deciding begins on from now. The voter sets of referenda that are being decided were
+		/// fixed before, so they still hold it.
+		///
+		/// Emits `AnonymousVoterRevoked`.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(4, 2 + T::MerkleDepth::get() as u64)
+		)]
+		pub fn revoke_anonymous_voter(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			leaf_index: u32,
+			path: BoundedVec<T::Hash, ConstU32<MAX_MERKLE_DEPTH>>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let commitment = <AnonymousVoterOf<T>>::get(&who).ok_or(Error::<T>::NotRegistered)?;
+			if Self::ensure_verified(&who).is_ok() {
+				return Err(Error::<T>::StillVerified.into())
+			}
+
+			let root = Self::remove_commitment(commitment, leaf_index, &path)?;
+			<AnonymousVoterOf<T>>::remove(&who);
+			Self::deposit_event(Event::AnonymousVoterRevoked { commitment, root });
+			Ok(())
+		}
+
+		/// Cast an anonymous ballot on a referendum that is being decided.
+		///
+		/// - `origin`: must be `Signed` by any account, which pays the quadratic price of the
+		///   votes. It does not need to be eligible to vote itself, so a fresh account keeps the
+		///   ballot apart from the voter's identity.
+		/// - `index`: The index of the referendum.
+		/// - `aye`: Whether the votes are in favour.
+		/// - `number_of_votes`: The number of quadratically priced votes.
+		/// - `nullifier`: Derived from the voter's key and `index`, so that every commitment can
+		///   cast one anonymous ballot per referendum.
+		/// - `proof`: Shows to `Membership` that the ballot comes from a commitment in the voter
+		///   set as it was when deciding on the referendum began. It must be bound to the
+		///   `ballot_signal` of the ballot and of the paying account.
+		///
+		/// Only possible when votes are priced in currency, as voice credits belong to an
+		/// identity. The reserve is unreserved with `remove_vote` once the referendum is over.
+		///
+		/// Emits `AnonymousVotesCast`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 3))]
+		pub fn vote_anonymously(
+			origin: OriginFor<T>,
+			index: ReferendumIndex,
+			aye: bool,
+			number_of_votes: u32,
+			nullifier: T::Hash,
+			proof: <T::Membership as VerifyMembership<T::Hash>>::Proof,
+		) -> DispatchResult {
+			if T::CommitReveal::get() {
+				return Err(Error::<T>::CommitRevealOnly.into())
+			}
+			if T::Pricing::get() != VotePricing::Currency {
+				return Err(Error::<T>::NotCurrencyPriced.into())
+			}
+			if number_of_votes == 0u32 {
+				return Err(Error::<T>::ZeroVote.into())
+			}
+			Self::ensure_votes_per_call(number_of_votes)?;
+
+			let who = ensure_signed(origin)?;
+			<pallet_referenda::Pallet<T>>::ensure_ongoing(index)?;
+			Self::ensure_not_maci(index)?;
+			let root = <AnonymousRootOf<T>>::get(index).ok_or(Error::<T>::NoVoterSet)?;
+			if <Nullifiers<T>>::contains_key(index, nullifier) {
+				return Err(Error::<T>::NullifierUsed.into())
+			}
+			if <AnonymousVotes<T>>::contains_key(index, &who) {
+				return Err(Error::<T>::AlreadyVoted.into())
+			}
+			let signal = Self::ballot_signal(index, aye, number_of_votes, &who);
+			if !T::Membership::verify(&root, index, &nullifier, &signal, &proof) {
+				return Err(Error::<T>::InvalidMembershipProof.into())
+			}
+
+			let cost = Self::cost_of(number_of_votes, index)?;
+			<T as Config>::Currency::reserve(&who, cost)?;
+			Self::deposit_event(Event::AmountReserved(cost));
+			Self::count_votes(index, aye, number_of_votes)?;
+
+			<Nullifiers<T>>::insert(index, nullifier, ());
+			<AnonymousVotes<T>>::insert(
+				index,
+				&who,
+				AccountVote { aye, votes: number_of_votes, cost },
+			);
+			Self::deposit_event(Event::AnonymousVotesCast {
+				aye,
+				number_of_votes,
+				index,
+				nullifier,
+			});
+			Ok(())
+		}
+
+		/// Have the deciding phase votes of a referendum in its launch phase sent as messages
+		/// encrypted to a coordinator, as in MACI (Minimal Anti-Collusion Infrastructure).
+		///
+		/// - `origin`: must be `MaciOrigin`.
+		/// - `index`: The index of the referendum.
+		/// - `coordinator`: The account that publishes the tally.
+		/// - `public_key`: The coordinator's X25519 public key.
+		///
+		/// Voters sign up with `maci_sign_up` and send their votes with `publish_message` until
+		/// the commit period is over. A message may also change the voter's key, which silently
+		/// invalidates any later message signed with the old key, so that a voter can not prove
+		/// to a briber how it voted. The coordinator publishes the tally with
+		/// `publish_maci_tally` during the reveal period. Whoever holds the coordinator's secret
+		/// key can recompute the tally and its commitment from the message log with the
+		/// `maci-verifier` tool.
+		///
+		/// Emits `MaciPollOpened`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1))]
+		pub fn open_maci_poll(
+			origin: OriginFor<T>,
+			index: ReferendumIndex,
+			coordinator: T::AccountId,
+			public_key: [u8; 32],
+		) -> DispatchResult {
+			T::MaciOrigin::ensure_origin(origin)?;
+			let status = <pallet_referenda::Pallet<T>>::ensure_ongoing(index)?;
+			// Votes that were counted already could not be told apart from the coordinator's
+			if status.decision_deposit.is_some() {
+				return Err(Error::<T>::LaunchPhaseOver.into())
+			}
+			if <MaciPolls<T>>::contains_key(index) {
+				return Err(Error::<T>::MaciPollExists.into())
+			}
+
+			<MaciPolls<T>>::insert(
+				index,
+				MaciPoll {
+					coordinator: coordinator.clone(),
+					public_key,
+					signups: 0,
+					messages: 0,
+					message_root: Default::default(),
+					tally: None,
+				},
+			);
+			Self::deposit_event(Event::MaciPollOpened { index, coordinator });
+			Ok(())
+		}
+
+		/// Sign up to send messages to the coordinator of a referendum.
+		///
+		/// - `origin`: must be `Signed` by an account that is eligible to vote, see
+		///   `ensure_eligible`.
+		/// - `index`: The index of the referendum, whose commit period must not be over.
+		/// - `public_key`: The ed25519 public key that the voter's first message is signed with.
+		///
+		/// The voter's state index, which its messages refer to, is emitted in `MaciSignedUp`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 3))]
+		pub fn maci_sign_up(
+			origin: OriginFor<T>,
+			index: ReferendumIndex,
+			public_key: [u8; 32],
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_eligible(&who)?;
+			let mut poll = <MaciPolls<T>>::get(index).ok_or(Error::<T>::NotMaciPoll)?;
+			Self::ensure_message_period(index)?;
+			if <MaciSignups<T>>::contains_key(index, &who) {
+				return Err(Error::<T>::AlreadySignedUp.into())
+			}
+
+			let state_index = poll.signups;
+			poll.signups = poll.signups.saturating_add(1);
+			<MaciSignups<T>>::insert(index, &who, state_index);
+			<MaciVoterKeys<T>>::insert(index, state_index, public_key);
+			<MaciPolls<T>>::insert(index, poll);
+			Self::deposit_event(Event::MaciSignedUp { index, state_index, public_key });
+			Ok(())
+		}
+
+		/// Publish a message to the coordinator of a referendum.
+		///
+		/// - `origin`: must be `Signed`, by any account, as the message is signed by the voter's
+		///   key inside.
+		/// - `index`: The index of the referendum, whose commit period must not be over.
+		/// - `ephemeral_key`: The X25519 public key that the message is encrypted with, together
+		///   with the coordinator's key.
+		/// - `data`: The encrypted command.
+		///
+		/// The chain can not tell a vote from a key change, nor whether the message is valid.
+		///
+		/// Emits `MaciMessagePublished`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 2))]
+		pub fn publish_message(
+			origin: OriginFor<T>,
+			index: ReferendumIndex,
+			ephemeral_key: [u8; 32],
+			data: BoundedVec<u8, T::MaxMessageLen>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let mut poll = <MaciPolls<T>>::get(index).ok_or(Error::<T>::NotMaciPoll)?;
+			Self::ensure_message_period(index)?;
+			if poll.messages >= T::MaxMessages::get() {
+				return Err(Error::<T>::TooManyMessages.into())
+			}
+
+			let position = poll.messages;
+			poll.messages += 1;
+			poll.message_root = T::Hashing::hash_of(&(poll.message_root, ephemeral_key, &data));
+			<MaciMessages<T>>::insert(index, position, (ephemeral_key, data));
+			<MaciPolls<T>>::insert(index, poll);
+			Self::deposit_event(Event::MaciMessagePublished { index, position });
+			Ok(())
+		}
+
+		/// Publish the tally of a referendum's messages, which counts it into the referendum.
+		///
+		/// - `origin`: must be `Signed` by the referendum's coordinator.
+		/// - `index`: The index of the referendum, which must be in its reveal period.
+		/// - `ayes`, `nays`, `supporters`: The tally, of the last valid vote of every voter.
+		/// - `commitment`: The commitment to the final voter states, as computed by the
+		///   `maci-verifier` tool.
+		///
+		/// The chain can not check the tally, since only the coordinator can decrypt the
+		/// messages. It only rejects a tally that no votes of the poll's voters could add up to,
+		/// given `MaciVoiceCredits`. The tally is stored with a commitment to it, `commitment`,
+		/// the poll's message root and `MaciVoiceCredits`. That binds what the coordinator
+		/// published to the messages, but does not prove it was counted from them: `commitment`
+		/// is salted with the coordinator's secret key, so only whoever holds that key can check
+		/// the tally, by recomputing the commitment with `maci-verifier`.
+		///
+		/// Emits `MaciTallyPublished`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn publish_maci_tally(
+			origin: OriginFor<T>,
+			index: ReferendumIndex,
+			ayes: u32,
+			nays: u32,
+			supporters: u32,
+			commitment: T::Hash,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut poll = <MaciPolls<T>>::get(index).ok_or(Error::<T>::NotMaciPoll)?;
+			if who != poll.coordinator {
+				return Err(Error::<T>::NotCoordinator.into())
+			}
+			if poll.tally.is_some() {
+				return Err(Error::<T>::TallyAlreadyPublished.into())
+			}
+			let (commit_end, reveal_end) = Self::ballot_periods(index)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+			if now < commit_end || now >= reveal_end {
+				return Err(Error::<T>::NotRevealPhase.into())
+			}
+			let voice_credits = T::MaciVoiceCredits::get();
+			// Every supporter cast at least one vote in favour, and no voter more than it can
+			// afford
+			let max_votes = voice_credits.integer_sqrt() as u64;
+			let others = poll.signups.checked_sub(supporters).ok_or(Error::<T>::ImplausibleTally)?;
+			if ayes < supporters ||
+				ayes as u64 > supporters as u64 * max_votes ||
+				nays as u64 > others as u64 * max_votes
+			{
+				return Err(Error::<T>::ImplausibleTally.into())
+			}
+
+			T::Polls::try_access_poll(index, |poll_status| match poll_status {
+				PollStatus::Ongoing(tally, _) => {
+					tally.add_totals(ayes, nays, supporters);
+					Ok(())
+				},
+				_ => Err(pallet_referenda::Error::<T>::NotOngoing.into()),
+			})?;
+
+			let tally_commitment = T::Hashing::hash_of(&(
+				index,
+				poll.message_root,
+				voice_credits,
+				ayes,
+				nays,
+				supporters,
+				commitment,
+			));
+			let tally = MaciTally { ayes, nays, supporters, commitment, tally_commitment };
+			poll.tally = Some(tally.clone());
+			<MaciPolls<T>>::insert(index, poll);
+			Self::deposit_event(Event::MaciTallyPublished { index, tally });
+			Ok(())
+		}
+
+		/// Relay a deciding phase vote that its voter signed, so that the voter need not pay for
+		/// the transaction.
+		///
+		/// - `origin`: must be `None`. The call is validated through `ValidateUnsigned`, so any
+		///   relayer can submit it.
+		/// - `vote`: The vote, which must not have expired, and must carry the voter's relay
+		///   nonce.
+		/// - `signature`: The voter's signature of the `relay_payload` of `vote`.
+		///
+		/// The vote is cast as with `vote_aye` or `vote_nay`, so the voter must be eligible and
+		/// pay the price of the votes. The nonce is used up even if the vote can not be cast, so
+		/// that a failing vote can not be relayed again.
+		///
+		/// Emits `VoteRelayed`, and `VotesCast` if the vote was cast.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 4))]
+		pub fn relay_vote(
+			origin: OriginFor<T>,
+			vote: RelayedVoteOf<T>,
+			signature: T::OffchainSignature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::check_relayed_vote(&vote, &signature)?;
+
+			let result = Self::cast_relayed_vote(&vote);
+			Self::deposit_event(Event::VoteRelayed {
+				voter: vote.voter,
+				nonce: vote.nonce,
+				result,
+			});
+			Ok(())
+		}
+
+		/// Submit a batch of deciding phase votes that their voters signed, such as ballots
+		/// collected off chain.
+		///
+		/// - `origin`: must be `Signed` by the aggregator, which pays for the transaction.
+		/// - `index`: The index of the referendum that every ballot must be for.
+		/// - `ballots`: Up to `MaxBallotsPerBatch` votes with their voters' signatures, as for
+		///   `relay_vote`.
+		///
+		/// Every ballot is checked and cast on its own, as with `relay_vote`, so each voter pays
+		/// the price of its own votes. A ballot that fails is reverted and reported, without
+		/// reverting the rest of the batch.
+		///
+		/// Emits `BallotBatchSubmitted`, and `VotesCast` for every ballot that was cast.
+		#[pallet::weight(
+			(10_000 + T::DbWeight::get().reads_writes(5, 4)).saturating_mul(ballots.len() as u64)
+		)]
+		pub fn submit_ballot_batch(
+			origin: OriginFor<T>,
+			index: ReferendumIndex,
+			ballots: BallotBatchOf<T>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			let results = ballots
+				.into_iter()
+				.map(|(vote, signature)| {
+					let result = if vote.index != index {
+						Err(Error::<T>::BallotForOtherReferendum.into())
+					} else {
+						Self::check_relayed_vote(&vote, &signature)
+							.map_err(Into::into)
+							.and_then(|()| Self::cast_relayed_vote(&vote))
+					};
+					(vote.voter, result)
+				})
+				.collect();
+			Self::deposit_event(Event::BallotBatchSubmitted { index, results });
+			Ok(())
+		}
+
+		/// Unreserve the funds behind a deciding phase vote once the referendum is over.
+		///
+		/// - `origin`: must be `Signed` by the account that voted, or that paid for an anonymous
+		///   ballot.
+		/// - `index`: The index of a referendum that is no longer ongoing.
+		///
+		/// Emits `AmountUnreserved`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1))]
+		pub fn remove_vote(origin: OriginFor<T>, index: ReferendumIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			if <pallet_referenda::Pallet<T>>::is_ongoing(index) {
+				return Err(Error::<T>::StillOngoing.into())
+			}
+
+			let vote = <VotingFor<T>>::take(index, &who)
+				.or_else(|| <AnonymousVotes<T>>::take(index, &who))
+				.ok_or(Error::<T>::NotVoted)?;
+			Self::release(&who, vote.cost);
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		/// Accepts relayed votes that `relay_vote` would accept, from voters that are eligible
+		/// and could cast them now: the referendum is deciding, the voter has not voted on it
+		/// yet, and can afford the votes. A vote with a future nonce waits in the pool for the
+		/// voter's earlier votes.
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let (vote, signature) = match call {
+				Call::relay_vote { vote, signature } => (vote, signature),
+				_ => return InvalidTransaction::Call.into(),
+			};
+			match Self::check_relayed_vote(vote, signature) {
+				Ok(()) | Err(Error::<T>::FutureRelayNonce) => (),
+				Err(Error::<T>::RelayExpired) | Err(Error::<T>::StaleRelayNonce) =>
+					return InvalidTransaction::Stale.into(),
+				Err(_) => return InvalidTransaction::BadProof.into(),
+			}
+			if Self::ensure_eligible(&vote.voter).is_err() {
+				return InvalidTransaction::BadSigner.into()
+			}
+			// Otherwise anyone could fill blocks with signed votes that fail for free
+			let cost = match Self::ensure_can_vote(&vote.voter, vote.votes, vote.index) {
+				Ok(cost) => cost,
+				Err(_) => return InvalidTransaction::Call.into(),
+			};
+			if !Self::can_afford(&vote.voter, cost) {
+				return InvalidTransaction::Payment.into()
+			}
+
+			let mut validity = ValidTransaction::with_tag_prefix("QvRelayedVote")
+				.priority(T::RelayedVotePriority::get())
+				.and_provides((vote.voter.clone(), vote.nonce))
+				.longevity(
+					vote.expiry
+						.saturating_sub(<frame_system::Pallet<T>>::block_number())
+						.saturated_into::<u64>()
+						.saturating_add(1),
+				)
+				.propagate(true);
+			if vote.nonce > <RelayNonce<T>>::get(&vote.voter) {
+				validity = validity.and_requires((vote.voter.clone(), vote.nonce - 1));
+			}
+			validity.build()
+		}
+	}
+
+	/// The number of claimed identities, as the size of the electorate.
+	///
+	/// Accounts that are eligible through a registrar's judgement are not counted, see
+	/// `EligibleVoters` for an electorate that counts them too.
+	pub struct ClaimedIdentities<T>(PhantomData<T>);
+	impl<T: Config> Get<u32> for ClaimedIdentities<T> {
+		fn get() -> u32 {
+			<ClaimedIdentityCount<T>>::get()
+		}
+	}
+
+	/// The number of claimed identities and of `VerifiedVoters`, as the size of the electorate.
+	///
+	/// An account verified by a registrar is counted from when it is registered with
+	/// `register_voter`, until it is forgotten with `forget_voter`.
+	pub struct EligibleVoters<T>(PhantomData<T>);
+	impl<T: Config> Get<u32> for EligibleVoters<T> {
+		fn get() -> u32 {
+			<ClaimedIdentityCount<T>>::get().saturating_add(<VerifiedVoterCount<T>>::get())
+		}
+	}
+
+	/// Helper functions
+	impl<T: Config> Pallet<T> {
+		/// Reserves an amount of token for a user, or spends as many of its voice credits.
+		///
+		/// The user must be verified, see `ensure_eligible`.
+		pub fn reserve_an_amount_of_token(
+			origin: OriginFor<T>,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_eligible(&who)?;
+			match T::Pricing::get() {
+				VotePricing::Currency => {
+					// If funds are too low and Err will be returned
+					<T as Config>::Currency::reserve(&who, amount)?;
+					Self::deposit_event(Event::AmountReserved(amount));
+				},
+				VotePricing::VoiceCredits => {
+					let mut budget = Self::budget_of(&who);
+					budget.left = budget
+						.left
+						.checked_sub(&amount)
+						.ok_or(Error::<T>::InsufficientVoiceCredits)?;
+					<VoiceCredits<T>>::insert(&who, budget);
+					Self::deposit_event(Event::VoiceCreditsSpent(amount));
+				},
+			}
+			Ok(())
+		}
+
+		/// Checks that `who` is eligible to vote: it must have claimed an identity, or one of the
+		/// `TrustedRegistrars` must have judged its identity `Reasonable` or `KnownGood`.
+		///
+		/// Anyone can set an identity on itself, so that alone does not make an account eligible.
+		pub fn ensure_verified(who: &T::AccountId) -> DispatchResult {
+			if <ClaimedIdentityOf<T>>::contains_key(who) {
+				return Ok(())
+			}
+			let registration =
+				pallet_identity::Pallet::<T>::identity(who).ok_or(Error::<T>::NoIdentity)?;
+			let trusted = T::TrustedRegistrars::get();
+			let verified = registration.judgements.iter().any(|(registrar, judgement)| {
+				trusted.contains(registrar) &&
+					matches!(judgement, Judgement::Reasonable | Judgement::KnownGood)
+			});
+			if !verified {
+				return Err(Error::<T>::NotVerified.into())
+			}
+			Ok(())
+		}
+
+		/// Checks that `who` is eligible to vote, as `ensure_verified`, and is counted into the
+		/// electorate: an account that a registrar verified must be registered with
+		/// `register_voter` first.
+		pub fn ensure_eligible(who: &T::AccountId) -> DispatchResult {
+			Self::ensure_verified(who)?;
+			if !<ClaimedIdentityOf<T>>::contains_key(who) &&
+				!<VerifiedVoters<T>>::contains_key(who)
+			{
+				return Err(Error::<T>::NotVerifiedVoter.into())
+			}
+			Ok(())
+		}
+
+		/// Takes `who` out of `VerifiedVoters`, if it is in there.
+		fn forget_verified_voter(who: &T::AccountId) {
+			if <VerifiedVoters<T>>::take(who).is_some() {
+				<VerifiedVoterCount<T>>::mutate(|count| *count = count.saturating_sub(1));
+			}
+		}
+
+		/// The voice credits `who` has left to spend on votes in the current epoch.
+		pub fn voice_credits(who: &T::AccountId) -> BalanceOf<T> {
+			Self::budget_of(who).left
+		}
+
+		/// The voice credit budget of `who` for the current epoch.
+		///
+		/// A budget from an earlier epoch is refilled once, however many epochs passed since, to
+		/// the allotment plus the carry-over, capped at `MaxCarryOver`.
+		fn budget_of(who: &T::AccountId) -> VoiceCreditBudget<BalanceOf<T>> {
+			let epoch = <CurrentEpoch<T>>::get();
+			let allotment = T::VoiceCreditAllotment::get();
+			let budget = match <VoiceCredits<T>>::get(who) {
+				Some(budget) => budget,
+				None => return VoiceCreditBudget { epoch, left: allotment },
+			};
+
+			let elapsed = epoch.saturating_sub(budget.epoch);
+			if elapsed == 0 {
+				return budget
+			}
+			let carry_over = Self::carry_over(budget.left, elapsed).min(T::MaxCarryOver::get());
+			VoiceCreditBudget { epoch, left: allotment.saturating_add(carry_over) }
+		}
+
+		/// What is carried over from `left` unspent voice credits into the epoch `elapsed`
+		/// epochs later, when none are spent in between. That is the carry-over of `left`, and
+		/// of the allotment of every epoch in between, compounded:
+		/// `c^elapsed * left + (c + c^2 + ... + c^(elapsed - 1)) * allotment`.
+		fn carry_over(left: BalanceOf<T>, elapsed: u32) -> BalanceOf<T> {
+			let rate = T::CarryOver::get();
+			let allotment = T::VoiceCreditAllotment::get();
+			let from_left = rate.saturating_pow(elapsed as usize).mul_floor(left);
+			let between = BalanceOf::<T>::from(elapsed.saturating_sub(1));
+			let from_allotments = if rate.is_one() {
+				allotment.saturating_mul(between)
+			} else {
+				// c + c^2 + ... + c^(n - 1) = c * (1 - c^(n - 1)) / (1 - c)
+				let series = rate * rate.saturating_pow(elapsed as usize - 1).left_from_one();
+				rate.left_from_one()
+					.saturating_reciprocal_mul_floor(series.mul_floor(allotment))
+			};
+			from_left.saturating_add(from_allotments)
+		}
+
+		/// Whether `who` has the free funds, or the voice credits, to pay `amount` for votes.
+		fn can_afford(who: &T::AccountId, amount: BalanceOf<T>) -> bool {
+			match T::Pricing::get() {
+				VotePricing::Currency => <T as Config>::Currency::can_reserve(who, amount),
+				VotePricing::VoiceCredits => Self::voice_credits(who) >= amount,
+			}
+		}
+
+		/// Gives back what `who` paid for votes that no longer count: unreserves `amount`, or
+		/// refunds as many voice credits into the budget of the current epoch.
+		fn release(who: &T::AccountId, amount: BalanceOf<T>) {
+			if amount.is_zero() {
+				return
+			}
+			match T::Pricing::get() {
+				VotePricing::Currency => {
+					<T as Config>::Currency::unreserve(who, amount);
+					Self::deposit_event(Event::AmountUnreserved(amount));
+				},
+				VotePricing::VoiceCredits => {
+					let mut budget = Self::budget_of(who);
+					budget.left = budget.left.saturating_add(amount);
+					<VoiceCredits<T>>::insert(who, budget);
+					Self::deposit_event(Event::VoiceCreditsRefunded(amount));
+				},
+			}
+		}
+
+		/// Takes what `who` paid for votes that it loses: slashes `amount` of its reserve, or
+		/// burns as many of the voice credits it spent.
+		fn forfeit(who: &T::AccountId, amount: BalanceOf<T>) {
+			if amount.is_zero() {
+				return
+			}
+			match T::Pricing::get() {
+				VotePricing::Currency => {
+					let (imbalance, _) = <T as Config>::Currency::slash_reserved(who, amount);
+					<T as Config>::Slash::on_unbalanced(imbalance);
+					Self::deposit_event(Event::AmountSlashed(amount));
+				},
+				// The voice credits were spent when the votes were cast
+				VotePricing::VoiceCredits => Self::deposit_event(Event::VoiceCreditsBurned(amount)),
+			}
+		}
+
+		/// Whether the cost of a backer's launch votes is held by pallet_referenda as the
+		/// Decision Deposit, rather than by this pallet.
+		fn held_by_referenda(backer: &BackerRecord<BalanceOf<T>>) -> bool {
+			backer.decision_deposit && T::Pricing::get() == VotePricing::Currency
+		}
+
+		/// Checks that no more than `MaxVotesPerCall` votes are cast in a single call.
+		fn ensure_votes_per_call(number_of_votes: u32) -> DispatchResult {
+			if number_of_votes > T::MaxVotesPerCall::get() {
+				return Err(Error::<T>::TooManyVotes.into())
+			}
+			Ok(())
+		}
+
+		/// The price of `votes` on ongoing referendum `index`, according to its track's curve.
+		fn cost_of(votes: u32, index: ReferendumIndex) -> Result<BalanceOf<T>, DispatchError> {
+			let track = <pallet_referenda::Pallet<T>>::referendum_view(index)
+				.and_then(|view| view.ongoing)
+				.ok_or(pallet_referenda::Error::<T>::NotOngoing)?
+				.track;
+			T::VoteCost::cost(votes, track).ok_or_else(|| Error::<T>::VoteCostOverflow.into())
+		}
+
+		/// Checks that a relayed vote has not expired, carries the voter's relay nonce, and is
+		/// signed by the voter.
+		fn check_relayed_vote(
+			vote: &RelayedVoteOf<T>,
+			signature: &T::OffchainSignature,
+		) -> Result<(), Error<T>> {
+			if <frame_system::Pallet<T>>::block_number() > vote.expiry {
+				return Err(Error::<T>::RelayExpired)
+			}
+			let nonce = <RelayNonce<T>>::get(&vote.voter);
+			if vote.nonce < nonce {
+				return Err(Error::<T>::StaleRelayNonce)
+			}
+			if vote.nonce > nonce {
+				return Err(Error::<T>::FutureRelayNonce)
+			}
+			if !signature.verify(&Self::relay_payload(vote)[..], &vote.voter) {
+				return Err(Error::<T>::BadRelaySignature)
+			}
+			Ok(())
+		}
+
+		/// Uses up the relay nonce of a checked relayed vote, and casts it. The vote is reverted
+		/// if it can not be cast, but the nonce stays used up.
+		fn cast_relayed_vote(vote: &RelayedVoteOf<T>) -> DispatchResult {
+			<RelayNonce<T>>::insert(&vote.voter, vote.nonce.saturating_add(1));
+			with_storage_layer(|| {
+				Self::do_vote(
+					RawOrigin::Signed(vote.voter.clone()).into(),
+					vote.aye,
+					vote.votes,
+					vote.index,
+				)
+			})
+		}
+
+		/// Checks that votes on referendum `index` are not sent as messages to a coordinator.
+		fn ensure_not_maci(index: ReferendumIndex) -> DispatchResult {
+			if <MaciPolls<T>>::contains_key(index) {
+				return Err(Error::<T>::MaciPollOnly.into())
+			}
+			Ok(())
+		}
+
+		/// Checks that messages can be sent on ongoing referendum `index`: deciding on it has not
+		/// begun, or its commit period is not over.
+		fn ensure_message_period(index: ReferendumIndex) -> DispatchResult {
+			let ongoing = <pallet_referenda::Pallet<T>>::referendum_view(index)
+				.and_then(|view| view.ongoing)
+				.ok_or(pallet_referenda::Error::<T>::NotOngoing)?;
+			if let Some(since) = ongoing.deciding_since {
+				let now = <frame_system::Pallet<T>>::block_number();
+				if now >= since.saturating_add(T::CommitPeriod::get()) {
+					return Err(Error::<T>::MessagePeriodOver.into())
+				}
+			}
+			Ok(())
+		}
+
+		/// The ends of the commit period and of the reveal period of ongoing referendum `index`,
+		/// which must be deciding.
+		fn ballot_periods(
+			index: ReferendumIndex,
+		) -> Result<(T::BlockNumber, T::BlockNumber), DispatchError> {
+			let ongoing = <pallet_referenda::Pallet<T>>::referendum_view(index)
+				.and_then(|view| view.ongoing)
+				.ok_or(pallet_referenda::Error::<T>::NotOngoing)?;
+			let since = ongoing.deciding_since.ok_or(Error::<T>::NotCommitPhase)?;
+			let commit_end = since.saturating_add(T::CommitPeriod::get());
+			Ok((commit_end, commit_end.saturating_add(T::RevealPeriod::get())))
+		}
+
+		/// Records `backer` as the launch backing of `who` in referendum `index`, and updates
+		/// the referendum's totals with the change in votes.
+		fn set_backer(
+			index: ReferendumIndex,
+			who: &T::AccountId,
+			backer: BackerRecord<BalanceOf<T>>,
+		) -> DispatchResult {
+			<LaunchTotals<T>>::try_mutate(index, |totals| -> DispatchResult {
+				match <Depositors<T>>::get(index, who) {
+					Some(previous) => totals.votes = totals.votes.saturating_sub(previous.votes),
+					None if totals.backers >= T::MaxBackers::get() =>
+						return Err(Error::<T>::TooManyBackers.into()),
+					None => totals.backers += 1,
+				}
+				totals.votes = totals.votes.saturating_add(backer.votes);
+				<Depositors<T>>::insert(index, who, backer);
+				Ok(())
+			})
+		}
+
+		/// Removes `who` from the backers of referendum `index`, and its launch votes from the
+		/// referendum's totals.
+		fn remove_backer(index: ReferendumIndex, who: &T::AccountId) {
+			if let Some(backer) = <Depositors<T>>::take(index, who) {
+				<LaunchTotals<T>>::mutate(index, |totals| {
+					totals.backers = totals.backers.saturating_sub(1);
+					totals.votes = totals.votes.saturating_sub(backer.votes);
+				});
+			}
+		}
+
+		/// Checks that the proposal of the earlier referendum `index` may be initiated again.
+		///
+		/// That is never the case while the referendum is ongoing, or after it was approved or
+		/// killed.
+		fn ensure_resubmittable(index: ReferendumIndex) -> DispatchResult {
+			let view = match <pallet_referenda::Pallet<T>>::referendum_view(index) {
+				Some(view) => view,
+				None => return Ok(()),
+			};
+			let resubmittable = T::AllowResubmission::get() &&
+				matches!(
+					view.phase,
+					ReferendumPhase::Rejected |
+						ReferendumPhase::TimedOut |
+						ReferendumPhase::Cancelled
+				);
+			let concluded = match view.concluded {
+				Some(concluded) if resubmittable => concluded,
+				_ => return Err(Error::<T>::DuplicateProposal.into()),
+			};
+			let now = <frame_system::Pallet<T>>::block_number();
+			if now < concluded.saturating_add(T::ResubmissionCooldown::get()) {
+				return Err(Error::<T>::ResubmissionCoolingDown.into())
+			}
+			Ok(())
+		}
+
+		/// Counts `votes` into the tally of ongoing referendum `index`, in favour if `aye`.
+		fn count_votes(index: ReferendumIndex, aye: bool, votes: u32) -> DispatchResult {
+			T::Polls::try_access_poll(index, |poll_status| match poll_status {
+				PollStatus::Ongoing(tally, _) => {
+					if aye {
+						tally.add_aye(votes);
+					} else {
+						tally.add_nay(votes);
+					}
+					Ok(())
+				},
+				_ => Err(pallet_referenda::Error::<T>::NotOngoing.into()),
+			})
+		}
+
+		/// The signal that the proof of an anonymous ballot on referendum `index` is bound to,
+		/// which commits to the ballot, to the account `payer` that casts it, and to this chain.
+		pub fn ballot_signal(
+			index: ReferendumIndex,
+			aye: bool,
+			number_of_votes: u32,
+			payer: &T::AccountId,
+		) -> T::Hash {
+			let genesis_hash = <frame_system::Pallet<T>>::block_hash(T::BlockNumber::zero());
+			T::Hashing::hash_of(&(b"qv/anon", genesis_hash, index, aye, number_of_votes, payer))
+		}
+
+		/// What the voter of a relayed vote signs, which commits to the vote and to this chain.
+		pub fn relay_payload(vote: &RelayedVoteOf<T>) -> Vec<u8> {
+			let genesis_hash = <frame_system::Pallet<T>>::block_hash(T::BlockNumber::zero());
+			(b"qv/relay", genesis_hash, vote).encode()
+		}
+
+		/// Adds `commitment` to the voter set, as the next leaf of its Merkle tree, and returns
+		/// the new root.
+		///
+		/// Only the rightmost full subtree at each level is kept, so this hashes once or twice per
+		/// level rather than rehashing the whole tree.
+		fn insert_commitment(commitment: T::Hash) -> Result<T::Hash, DispatchError> {
+			let depth = T::MerkleDepth::get().min(MAX_MERKLE_DEPTH);
+			let position = <AnonymousVoterCount<T>>::get();
+			if (position as u64) >> depth != 0 {
+				return Err(Error::<T>::VoterSetFull.into())
+			}
+
+			let mut node = commitment;
+			// The root of an empty subtree at the current level
+			let mut empty = T::Hash::default();
+			let mut index = position;
+			for level in 0..depth {
+				node = if index % 2 == 0 {
+					<FilledSubtrees<T>>::insert(level, node);
+					merkle_node::<T::Hashing>(&node, &empty)
+				} else {
+					merkle_node::<T::Hashing>(&<FilledSubtrees<T>>::get(level), &node)
+				};
+				empty = merkle_node::<T::Hashing>(&empty, &empty);
+				index /= 2;
+			}
+			<AnonymousVoterCount<T>>::put(position + 1);
+			<AnonymousVoterRoot<T>>::put(node);
+			Ok(node)
+		}
+
+		/// Empties the leaf at `leaf_index` of the voter set, which must hold `commitment`, and
+		/// returns the new root. `path` must lead from the leaf to the current root.
+		///
+		/// The rightmost full subtrees that hold the leaf are updated as well, so that the
+		/// commitments added later are hashed together with the emptied leaf.
+		fn remove_commitment(
+			commitment: T::Hash,
+			leaf_index: u32,
+			path: &[T::Hash],
+		) -> Result<T::Hash, DispatchError> {
+			let depth = T::MerkleDepth::get().min(MAX_MERKLE_DEPTH);
+			let count = <AnonymousVoterCount<T>>::get();
+			if path.len() as u32 != depth || leaf_index >= count {
+				return Err(Error::<T>::InvalidMembershipProof.into())
+			}
+
+			let mut node = commitment;
+			let mut emptied = T::Hash::default();
+			let mut filled_subtrees = Vec::new();
+			let mut index = leaf_index;
+			// The position of the rightmost node at the current level
+			let mut last = count - 1;
+			for (level, sibling) in path.iter().enumerate() {
+				if index == last & !1 {
+					filled_subtrees.push((level as u32, emptied));
+				}
+				if index % 2 == 0 {
+					node = merkle_node::<T::Hashing>(&node, sibling);
+					emptied = merkle_node::<T::Hashing>(&emptied, sibling);
+				} else {
+					node = merkle_node::<T::Hashing>(sibling, &node);
+					emptied = merkle_node::<T::Hashing>(sibling, &emptied);
+				}
+				index /= 2;
+				last /= 2;
+			}
+			if node != <AnonymousVoterRoot<T>>::get() {
+				return Err(Error::<T>::InvalidMembershipProof.into())
+			}
+
+			for (level, subtree) in filled_subtrees {
+				<FilledSubtrees<T>>::insert(level, subtree);
+			}
+			<AnonymousVoterRoot<T>>::put(emptied);
+			Ok(emptied)
+		}
+
+		/// Reserves the quadratic price of `number_of_votes` and counts them into the tally of
+		/// referendum `index`.
+		///
+		/// The referendum's Decision Deposit must have been posted, which means that the launch
+		/// phase is over, and deciding on it must have begun.
+		fn do_vote(
+			origin: OriginFor<T>,
+			aye: bool,
+			number_of_votes: u32,
+			index: ReferendumIndex,
+		) -> DispatchResult {
+			let who = ensure_signed(origin.clone())?;
+			let cost = Self::ensure_can_vote(&who, number_of_votes, index)?;
+			Self::reserve_an_amount_of_token(origin, cost)?;
+
+			Self::count_votes(index, aye, number_of_votes)?;
+
+			<VotingFor<T>>::insert(index, &who, AccountVote { aye, votes: number_of_votes, cost });
+			Self::deposit_event(Event::VotesCast { aye, number_of_votes, index });
+			Ok(())
+		}
+
+		/// Checks that `who` can cast `number_of_votes` on referendum `index` with `do_vote`, and
+		/// returns their price. Whether `who` is eligible and can afford it is not checked.
+		fn ensure_can_vote(
+			who: &T::AccountId,
+			number_of_votes: u32,
+			index: ReferendumIndex,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			if number_of_votes == 0u32 {
+				return Err(Error::<T>::ZeroVote.into())
+			}
+
+			if T::CommitReveal::get() {
+				return Err(Error::<T>::CommitRevealOnly.into())
+			}
+
+			let status = <pallet_referenda::Pallet<T>>::ensure_ongoing(index)?;
+			if status.decision_deposit.is_none() {
+				return Err(Error::<T>::StillInLaunchPhase.into())
+			}
+			// Votes are counted once the voter set is fixed, so that an account can not vote
+			// directly and then register a commitment that votes anonymously as well
+			if !<AnonymousRootOf<T>>::contains_key(index) {
+				return Err(Error::<T>::NoVoterSet.into())
+			}
+			if <AnonymousVoterOf<T>>::contains_key(who) {
+				return Err(Error::<T>::RegisteredAnonymously.into())
+			}
+			Self::ensure_not_maci(index)?;
+			if <VotingFor<T>>::contains_key(index, who) {
+				return Err(Error::<T>::AlreadyVoted.into())
+			}
+
+			Self::ensure_votes_per_call(number_of_votes)?;
+			Self::cost_of(number_of_votes, index)
+		}
+
+		/// Unreserves an amount of token for a user, or refunds as many of its voice credits.
+		///
+		/// The user need not be eligible to vote anymore: what it paid for votes is given back
+		/// regardless.
+		pub fn unreserve_an_amount_of_token(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::release(&who, amount);
+			Ok(())
+		}
+
+		/// Refund all Decision Deposits (launch votes) for a referendum.
+		///
+		/// - `origin`: must be `Root`
+		/// - `index`: The index of the submitted referendum whose Decision Deposit is yet to be
+		///   posted.
+		///
+		/// This function is triggered by the state machine inside pallet_referenda
+		/// (the service_referendum() function) through `ReferendumHooks`, whenever a referendum
+		/// concludes without being killed.
+		///
+		/// The splitting of the deposits across several origins, and the quadratic pricing,
+		/// are not implemented inside pallet-referenda.
+		/// Therefore we implement this book-keeping ourselves inside pallet-qv.
+		/// We build on top of
+		/// Referenda::refund_decision_deposit()
+		/// and want to stay as close to its behaviour as we can.
+		pub fn refund_launch_votes(origin: OriginFor<T>, index: ReferendumIndex) -> DispatchResult {
+			ensure_root(origin)?;
+			if <pallet_referenda::Pallet<T>>::is_ongoing(index) {
+				return Err(Error::<T>::StillOngoing.into())
+			}
+			Self::release_launch_votes(index);
+			Ok(())
+		}
+
+		/// Refund the launch votes of concluded referendum `index`, see `refund_launch_votes`.
+		///
+		/// Its weight is bounded by `MaxBackers`, see `max_conclusion_weight`.
+		fn release_launch_votes(index: ReferendumIndex) {
+			<LaunchTotals<T>>::remove(index);
+			for (who, backer) in <Depositors<T>>::drain_prefix(index) {
+				if backer.decision_deposit {
+					// Anyone may have refunded the Decision Deposit through pallet_referenda
+					// already, in which case it returns NoDeposit
+					let refunded = <pallet_referenda::Pallet<T>>::refund_decision_deposit(
+						RawOrigin::Root.into(),
+						index,
+					);
+					debug_assert!(
+						refunded.is_ok() ||
+							refunded == Err(pallet_referenda::Error::<T>::NoDeposit.into()),
+						"LOGIC ERROR: the Decision Deposit of a concluded referendum was kept"
+					);
+				}
+				if !Self::held_by_referenda(&backer) && !backer.cost.is_zero() {
+					// The initiator backs with zero votes, its deposit is handled by
+					// pallet_referenda
+					Self::release(&who, backer.cost);
+				}
+			}
+		}
+
+		/// Slash all launch votes for a killed referendum.
+		///
+		/// Launch votes that were placed as the Decision Deposit are slashed by pallet_referenda.
+		fn slash_launch_votes(index: ReferendumIndex) {
+			<LaunchTotals<T>>::remove(index);
+			for (who, backer) in <Depositors<T>>::drain_prefix(index) {
+				if !Self::held_by_referenda(&backer) {
+					Self::forfeit(&who, backer.cost);
+				}
+			}
+		}
+	}
+
+	/// The electorate is snapshotted into the tally when deciding begins, and the voter set of
+	/// anonymous ballots is fixed. Launch votes are refunded when a referendum concludes, and
+	/// slashed when it is killed.
+	impl<T: Config> pallet_referenda::ReferendumHooks<T::PollTally> for Pallet<T> {
+		fn on_decision_started(index: ReferendumIndex, tally: &mut T::PollTally) {
+			tally.snapshot_electorate(T::Electorate::get());
+			<AnonymousRootOf<T>>::insert(index, <AnonymousVoterRoot<T>>::get());
+		}
+
+		fn on_approved(index: ReferendumIndex) {
+			Self::release_launch_votes(index);
+		}
+
+		fn on_rejected(index: ReferendumIndex) {
+			Self::release_launch_votes(index);
+		}
+
+		fn on_timed_out(index: ReferendumIndex) {
+			Self::release_launch_votes(index);
+		}
+
+		fn on_cancelled(index: ReferendumIndex) {
+			Self::release_launch_votes(index);
+		}
+
+		fn on_killed(index: ReferendumIndex) {
+			Self::slash_launch_votes(index);
+		}
+
+		/// Every one of at most `MaxBackers` launch backers is taken out of `Depositors` and
+		/// refunded or slashed, and the Decision Deposit is refunded once.
+		fn max_conclusion_weight() -> Weight {
+			let backers = T::MaxBackers::get() as u64;
+			10_000 + T::DbWeight::get().reads_writes(2 + 2 * backers, 3 + 2 * backers)
+		}
 	}
 }
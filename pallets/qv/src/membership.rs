@@ -0,0 +1,125 @@
+//! Proofs that an anonymous ballot was cast by a member of the voter set.
+
+use codec::{Decode, Encode};
+use frame_support::{traits::ConstU32, BoundedVec, Parameter};
+use pallet_referenda::ReferendumIndex;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{Hash, IdentifyAccount, Verify},
+	RuntimeDebug,
+};
+use sp_std::marker::PhantomData;
+
+/// The deepest Merkle tree that a [`MerkleProof`] can prove membership of.
+pub const MAX_MERKLE_DEPTH: u32 = 32;
+
+/// Verifies that an anonymous ballot was cast by the holder of a commitment in the voter set.
+///
+/// Only a zero-knowledge verifier, which proves membership without telling which commitment cast
+/// the ballot, keeps ballots anonymous. [`MerkleMembership`] does not, and `()` turns anonymous
+/// ballots off.
+pub trait VerifyMembership<Hash> {
+	/// The proof that comes with an anonymous ballot.
+	type Proof: Parameter;
+
+	/// Whether `proof` shows that the holder of a commitment in the voter set with Merkle root
+	/// `root` cast the ballot with `nullifier` on referendum `index`.
+	///
+	/// `signal` is the hash of the ballot and of the account that pays for it. The proof must be
+	/// bound to it, so that it can not be taken out of a pending ballot and used for another.
+	fn verify(
+		root: &Hash,
+		index: ReferendumIndex,
+		nullifier: &Hash,
+		signal: &Hash,
+		proof: &Self::Proof,
+	) -> bool;
+}
+
+/// Rejects every proof, so no anonymous ballots can be cast.
+impl<Hash> VerifyMembership<Hash> for () {
+	type Proof = ();
+
+	fn verify(_: &Hash, _: ReferendumIndex, _: &Hash, _: &Hash, _: &Self::Proof) -> bool {
+		false
+	}
+}
+
+/// The key behind a commitment in the voter set, its signature of the ballot, and the Merkle
+/// path from the commitment to the root.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct MerkleProof<Hash, Key, Signature> {
+	/// The key that the commitment is the hash of. Its secret part never leaves the voter.
+	pub key: Key,
+	/// The key's signature of the ballot's signal.
+	pub signature: Signature,
+	/// The position of the commitment among the leaves of the tree.
+	pub leaf_index: u32,
+	/// The siblings of the nodes on the way from the commitment up to the root.
+	pub path: BoundedVec<Hash, ConstU32<MAX_MERKLE_DEPTH>>,
+}
+
+/// The [`MerkleProof`] of [`MerkleMembership`] with `Hashing` and `Signature`.
+pub type MerkleProofOf<Hashing, Signature> = MerkleProof<
+	<Hashing as Hash>::Output,
+	<<Signature as Verify>::Signer as IdentifyAccount>::AccountId,
+	Signature,
+>;
+
+/// Verifies a [`MerkleProof`] of membership, with the commitment `hash(key)` and the nullifier
+/// `hash(key, index)`. The key signs the ballot's signal, so the proof can not be replayed with
+/// another ballot or payer.
+///
+/// The proof reveals the key, and with it which commitment cast the ballot. So the ballot is not
+/// linked to the account that pays for it, but it is linked to the account that registered the
+/// commitment. Hiding that as well takes a zero-knowledge verifier of the same statement, which
+/// is why runtimes should not rely on this one for anonymous ballots.
+pub struct MerkleMembership<Hashing, Signature>(PhantomData<(Hashing, Signature)>);
+impl<Hashing, Signature> VerifyMembership<Hashing::Output> for MerkleMembership<Hashing, Signature>
+where
+	Hashing: Hash,
+	Signature: Verify + Parameter,
+	<Signature::Signer as IdentifyAccount>::AccountId: Parameter,
+{
+	type Proof = MerkleProofOf<Hashing, Signature>;
+
+	fn verify(
+		root: &Hashing::Output,
+		index: ReferendumIndex,
+		nullifier: &Hashing::Output,
+		signal: &Hashing::Output,
+		proof: &Self::Proof,
+	) -> bool {
+		if Hashing::hash_of(&(&proof.key, index)) != *nullifier {
+			return false
+		}
+		if !proof.signature.verify(&signal.encode()[..], &proof.key) {
+			return false
+		}
+		let mut position = proof.leaf_index;
+		let mut node = Hashing::hash_of(&proof.key);
+		for sibling in proof.path.iter() {
+			node = if position % 2 == 0 {
+				merkle_node::<Hashing>(&node, sibling)
+			} else {
+				merkle_node::<Hashing>(sibling, &node)
+			};
+			position /= 2;
+		}
+		node == *root
+	}
+}
+
+/// The parent of two nodes of a Merkle tree.
+pub fn merkle_node<Hashing: Hash>(
+	left: &Hashing::Output,
+	right: &Hashing::Output,
+) -> Hashing::Output {
+	Hashing::hash_of(&(left, right))
+}
+
+/// The root of a Merkle subtree of height `level` without any commitments. An empty leaf is the
+/// default hash.
+pub fn empty_subtree<Hashing: Hash>(level: u32) -> Hashing::Output {
+	(0..level).fold(Default::default(), |node, _| merkle_node::<Hashing>(&node, &node))
+}
@@ -1,23 +1,22 @@
 use crate as pallet_qv;
 
 use crate::mock::system::{EnsureRoot, EnsureSignedBy};
-use codec::{Decode, Encode, MaxEncodedLen};
 use frame_support::{
-	ord_parameter_types,
-	pallet_prelude::TypeInfo,
-	parameter_types,
+	ord_parameter_types, parameter_types,
 	traits::{
-		ConstU16, ConstU32, ConstU64, EitherOfDiverse, EqualPrivilegeOnly, OriginTrait, VoteTally,
+		ConstU16, ConstU32, ConstU64, EitherOfDiverse, EqualPrivilegeOnly, OnInitialize,
+		OriginTrait,
 	},
 };
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
-	testing::Header,
+	testing::{Header, TestSignature, UintAuthorityId},
 	traits::{BlakeTwo256, IdentityLookup},
 	Perbill,
 };
 
+use pallet_qv::{Linear, MerkleMembership, Quadratic, QuadraticTally, VoteCostCurve, VotePricing};
 use pallet_referenda::{TrackInfo, TracksInfo};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -99,45 +98,70 @@ impl pallet_identity::Config for Test {
 	type WeightInfo = ();
 }
 
-const ONE_MONTH: u64 = 446400; // 31*24*60*60/6 = "One month" / "block time"
+pub const ONE_MONTH: u64 = 446400; // 31*24*60*60/6 = "One month" / "block time"
 
 pub struct TestTracksInfo;
 impl TracksInfo<u64, u64> for TestTracksInfo {
 	type Id = u8;
 	type Origin = <Origin as OriginTrait>::PalletsOrigin;
 	fn tracks() -> &'static [(Self::Id, TrackInfo<u64, u64>)] {
-		static DATA: [(u8, TrackInfo<u64, u64>); 1] = [(
-			// IMPORTANT STUFF, this is where we design our referenda.
-			// We try to match Votion's visions by configuring this right
-			0u8,
-			TrackInfo {
-				name: "votion",
-				max_deciding: 100_000, // This is how many referenda we can have at once
-				/// Amount that must be placed on deposit before a decision can be made.
-				decision_deposit: LAUNCH_DEPOSIT, // Need 1000 PWR to go from launch to voting
-				/// Amount of time this must be submitted for before a decision can be made.
-				prepare_period: ONE_MONTH, // Don't think we will use the prepare period feture
-				/// Amount of time that a decision may take to be approved prior to
-				/// cancellation.
-				decision_period: ONE_MONTH,
-				/// Amount of time that the approval criteria must hold before it can be
-				/// approved.
-				confirm_period: 1,
-				/// Minimum amount of time that an approved proposal must be in the dispatch
-				/// queue.
-				min_enactment_period: 0,
-				min_approval: pallet_referenda::Curve::LinearDecreasing {
-					length: Perbill::one(), // Go flat at 0% almost from the start
-					floor: Perbill::zero(), // We want all referendums to "pass"
-					ceil: Perbill::from_percent(100),
+		static DATA: [(u8, TrackInfo<u64, u64>); 2] = [
+			(
+				// IMPORTANT STUFF, this is where we design our referenda.
+				// We try to match Votion's visions by configuring this right
+				0u8,
+				TrackInfo {
+					name: "votion",
+					max_deciding: 100_000, // This is how many referenda we can have at once
+					/// Amount that must be placed on deposit before a decision can be made.
+					decision_deposit: LAUNCH_DEPOSIT, // Need 1000 PWR to go from launch to voting
+					/// Amount of time this must be submitted for before a decision can be made.
+					prepare_period: ONE_MONTH, // Don't think we will use the prepare period feture
+					/// Amount of time that a decision may take to be approved prior to
+					/// cancellation.
+					decision_period: ONE_MONTH,
+					/// Amount of time that the approval criteria must hold before it can be
+					/// approved.
+					confirm_period: 1,
+					/// Minimum amount of time that an approved proposal must be in the dispatch
+					/// queue.
+					min_enactment_period: 0,
+					min_approval: pallet_referenda::Curve::LinearDecreasing {
+						length: Perbill::one(),
+						floor: Perbill::from_percent(50), // A majority of the quadratic votes must be ayes
+						ceil: Perbill::from_percent(100),
+					},
+					min_support: pallet_referenda::Curve::LinearDecreasing {
+						length: Perbill::one(), // Go flat at 0% almost from the start
+						floor: Perbill::zero(), // We want all referendums to "pass"
+						ceil: Perbill::from_percent(100),
+					},
 				},
-				min_support: pallet_referenda::Curve::LinearDecreasing {
-					length: Perbill::one(), // Go flat at 0% almost from the start
-					floor: Perbill::zero(), // We want all referendums to "pass"
-					ceil: Perbill::from_percent(100),
+			),
+			(
+				// A cheaper track for low-stakes proposals
+				1u8,
+				TrackInfo {
+					name: "none",
+					max_deciding: 100_000,
+					decision_deposit: LAUNCH_DEPOSIT / 10,
+					prepare_period: ONE_MONTH,
+					decision_period: ONE_MONTH,
+					confirm_period: 1,
+					min_enactment_period: 0,
+					min_approval: pallet_referenda::Curve::LinearDecreasing {
+						length: Perbill::one(),
+						floor: Perbill::from_percent(50),
+						ceil: Perbill::from_percent(100),
+					},
+					min_support: pallet_referenda::Curve::LinearDecreasing {
+						length: Perbill::one(),
+						floor: Perbill::zero(),
+						ceil: Perbill::from_percent(100),
+					},
 				},
-			},
-		)];
+			),
+		];
 		&DATA[..]
 	}
 	fn track_for(id: &Self::Origin) -> Result<Self::Id, ()> {
@@ -181,49 +205,15 @@ impl pallet_scheduler::Config for Test {
 	type NoPreimagePostponement = ConstU64<10>;
 }
 
-#[derive(Encode, Debug, Decode, TypeInfo, Eq, PartialEq, Clone, MaxEncodedLen)]
-pub struct Tally {
-	pub ayes: u32,
-	pub nays: u32,
-}
-
-impl<Class> VoteTally<u32, Class> for Tally {
-	fn new(_: Class) -> Self {
-		Self { ayes: 0, nays: 0 }
-	}
-
-	fn ayes(&self, _: Class) -> u32 {
-		self.ayes
-	}
-
-	fn support(&self, _: Class) -> Perbill {
-		Perbill::from_percent(self.ayes)
-	}
-
-	fn approval(&self, _: Class) -> Perbill {
-		if self.ayes + self.nays > 0 {
-			Perbill::from_rational(self.ayes, self.ayes + self.nays)
-		} else {
-			Perbill::zero()
+/// Votes are priced quadratically, except on the cheaper "none" track where they are linear.
+pub struct TestVoteCost;
+impl VoteCostCurve<u64, u8> for TestVoteCost {
+	fn cost(votes: u32, track: u8) -> Option<u64> {
+		match track {
+			1 => <Linear as VoteCostCurve<u64, u8>>::cost(votes, track),
+			_ => <Quadratic as VoteCostCurve<u64, u8>>::cost(votes, track),
 		}
 	}
-
-	#[cfg(feature = "runtime-benchmarks")]
-	fn unanimity(_: Class) -> Self {
-		Self { ayes: 100, nays: 0 }
-	}
-
-	#[cfg(feature = "runtime-benchmarks")]
-	fn rejection(_: Class) -> Self {
-		Self { ayes: 0, nays: 100 }
-	}
-
-	#[cfg(feature = "runtime-benchmarks")]
-	fn from_requirements(support: Perbill, approval: Perbill, _: Class) -> Self {
-		let ayes = support.mul_ceil(100u32);
-		let nays = ((ayes as u64) * 1_000_000_000u64 / approval.deconstruct() as u64) as u32 - ayes;
-		Self { ayes, nays }
-	}
 }
 
 impl pallet_referenda::Config for Test {
@@ -232,25 +222,80 @@ impl pallet_referenda::Config for Test {
 	type Event = Event;
 	type Scheduler = Scheduler;
 	type Currency = Balances;
-	type SubmitOrigin = frame_system::EnsureSigned<u64>;
+	type SubmitOrigin = frame_system::EnsureNever<u64>;
 	type CancelOrigin = EnsureRoot<u64>;
 	type KillOrigin = EnsureRoot<u64>;
 	type Slash = ();
 	type Votes = u32;
-	type Tally = Tally;
+	type Tally = QuadraticTally;
 	type SubmissionDeposit = ConstU64<1000>;
 	type MaxQueued = ConstU32<3>;
 	type UndecidingTimeout = ConstU64<ONE_MONTH>; // "one month in sec" / "6s block time"
 	type AlarmInterval = AlarmInterval;
 	type Tracks = TestTracksInfo;
+	type Hooks = Qv;
 }
 
 const LAUNCH_DEPOSIT: u64 = 1000;
+pub const MAX_VOTES_PER_CALL: u32 = 100_000;
+pub const REFERENDUM_LIFETIME: u64 = 2 * ONE_MONTH;
+pub const MIN_REFERENDUM_LIFETIME: u64 = 10;
+pub const MAX_REFERENDUM_LIFETIME: u64 = 6 * ONE_MONTH;
+pub const RESUBMISSION_COOLDOWN: u64 = 100;
+
+parameter_types! {
+	pub static AllowResubmission: bool = true;
+	pub static MaxBackers: u32 = 100;
+	pub static Electorate: u32 = 1;
+	pub static TrustedRegistrars: Vec<u32> = vec![0];
+	pub static Pricing: VotePricing = VotePricing::Currency;
+	pub static VoiceCreditAllotment: u64 = 1_000;
+	pub static EpochLength: u64 = 0;
+	pub static CarryOver: Perbill = Perbill::zero();
+	pub static MaxCarryOver: u64 = 0;
+	pub static CommitReveal: bool = false;
+	pub static CommitPeriod: u64 = 10;
+	pub static RevealPeriod: u64 = 10;
+	pub static ForfeitUnrevealed: bool = false;
+}
 
 impl pallet_qv::Config for Test {
 	type Event = Event;
+	type TrustedRegistrars = TrustedRegistrars;
+	type IdentityOrigin = EnsureRoot<u64>;
 	type Currency = Balances;
-	type LaunchDeposit = ConstU64<LAUNCH_DEPOSIT>;
+	type Pricing = Pricing;
+	type VoiceCreditAllotment = VoiceCreditAllotment;
+	type EpochLength = EpochLength;
+	type CarryOver = CarryOver;
+	type MaxCarryOver = MaxCarryOver;
+	type LaunchDeposit = ConstU32<{ LAUNCH_DEPOSIT as u32 }>;
+	type VoteCost = TestVoteCost;
+	type MaxVotesPerCall = ConstU32<MAX_VOTES_PER_CALL>;
+	type MaxBackers = MaxBackers;
+	type ReferendumLifetime = ConstU64<REFERENDUM_LIFETIME>;
+	type MinReferendumLifetime = ConstU64<MIN_REFERENDUM_LIFETIME>;
+	type MaxReferendumLifetime = ConstU64<MAX_REFERENDUM_LIFETIME>;
+	type AllowResubmission = AllowResubmission;
+	type ResubmissionCooldown = ConstU64<RESUBMISSION_COOLDOWN>;
+	type CommitReveal = CommitReveal;
+	type CommitPeriod = CommitPeriod;
+	type RevealPeriod = RevealPeriod;
+	type ForfeitUnrevealed = ForfeitUnrevealed;
+	type Membership = MerkleMembership<BlakeTwo256, TestSignature>;
+	type MerkleDepth = ConstU32<4>;
+	type MaciOrigin = EnsureRoot<u64>;
+	type MaxMessageLen = ConstU32<256>;
+	type MaxMessages = ConstU32<16>;
+	type MaciVoiceCredits = ConstU32<100>;
+	type OffchainSignature = TestSignature;
+	type OffchainPublic = UintAuthorityId;
+	type RelayedVotePriority = ConstU64<100>;
+	type MaxBallotsPerBatch = ConstU32<4>;
+	type Slash = ();
+	type PollTally = QuadraticTally;
+	type Electorate = Electorate;
+	type Polls = Referenda;
 }
 
 // Configure a mock runtime to test the pallet.
@@ -285,9 +330,26 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 			(30, 1010),
 			(31, 250_010), // 31 and 32 have many tokens just to allow shorter tests
 			(32, 250_010),
+			(40, 10_010), // 40 and 41 can afford 100 deciding phase votes
+			(41, 10_010),
 		],
 	}
 	.assimilate_storage(&mut t)
 	.unwrap();
 	t.into()
 }
+
+/// Advance to block `n`, servicing every scheduler agenda on the way.
+///
+/// Blocks without any agenda are skipped, since a referendum phase lasts for months.
+pub fn run_to(n: u64) {
+	while System::block_number() < n {
+		let now = System::block_number();
+		let next = pallet_scheduler::Agenda::<Test>::iter_keys()
+			.filter(|when| *when > now && *when <= n)
+			.min()
+			.unwrap_or(n);
+		System::set_block_number(next);
+		Scheduler::on_initialize(next);
+	}
+}
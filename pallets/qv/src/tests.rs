@@ -1,12 +1,30 @@
-use crate::{mock::*, Error};
-use frame_support::{assert_err, assert_noop, assert_ok, dispatch::RawOrigin, BoundedVec};
+use crate::{
+	empty_subtree, merkle_node, mock::*, quadratic_cost, AccumulateVotes, AnonymousVoterCount,
+	BackerRecord, ClaimState, ClaimedIdentities, Cubic, CurrentEpoch, Depositors, EligibleVoters,
+	Error, LaunchTally, Linear, MaciTally, MerkleMembership, MerkleProof, MerkleProofOf, Quadratic,
+	QuadraticTally, RationalPower, RelayedVote, VerifyMembership, VoteCostCurve, VotePricing,
+};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::{GetDispatchInfo, RawOrigin},
+	traits::{schedule::DispatchTime, ConstU32, Get, OnInitialize, VoteTally},
+	BoundedVec,
+};
 
+use frame_system::ensure_signed;
+use maci_verifier::{encrypt, process, public_key, Command, MessageLog};
 use pallet_balances::Error as BalancesError;
-use pallet_identity::{Data, IdentityInfo};
-use pallet_referenda::{Error as ReferendaError, ReferendumCount};
+use pallet_identity::{Data, IdentityInfo, Judgement};
+use pallet_referenda::{
+	Error as ReferendaError, ReferendumCount, ReferendumHooks, ReferendumPhase,
+};
+use sp_core::{ed25519, Pair, H256};
 use sp_runtime::{
-	traits::{BlakeTwo256, Hash},
-	DispatchError,
+	testing::TestSignature,
+	traits::{BlakeTwo256, Hash, ValidateUnsigned},
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidityError},
+	DispatchError, Perbill,
 };
 
 use crate::Event as QvEvent;
@@ -58,12 +76,93 @@ fn info() -> IdentityInfo<MaxAdditionalFields> {
 	}
 }
 
+/// Set an identity on `who`'s account, and have it judged by `registrar`, whose account is
+/// `registrar + 1`.
+fn set_judged_identity(who: Origin, registrar: u32, judgement: Judgement<u64>) {
+	let account = ensure_signed(who.clone()).unwrap();
+	assert_ok!(Identity::set_identity(who, Box::new(info())));
+	while Identity::registrars().len() as u32 <= registrar {
+		let registrar_account = Identity::registrars().len() as u64 + 1;
+		assert_ok!(Identity::add_registrar(RawOrigin::Root.into(), registrar_account));
+	}
+	let registrar_account = Origin::signed(registrar as u64 + 1);
+	assert_ok!(Identity::provide_judgement(registrar_account, registrar, account, judgement));
+}
+
+/// Set an identity on `who`'s account, have the trusted registrar judge it reasonable, and
+/// register the account as a voter if it is not counted into the electorate yet.
+fn set_verified_identity(who: Origin) {
+	let account = ensure_signed(who.clone()).unwrap();
+	set_judged_identity(who, 0, Judgement::Reasonable);
+	if Qv::ensure_eligible(&account).is_err() {
+		assert_ok!(Qv::register_voter(Origin::signed(1), account));
+	}
+}
+
+#[test]
+fn try_reserve_self_declared_identity() {
+	new_test_ext().execute_with(|| {
+		let who = Origin::signed(10);
+		assert_ok!(Identity::set_identity(who.clone(), Box::new(info())));
+
+		assert_noop!(Qv::reserve_an_amount_of_token(who, SMALL_AMOUNT), Error::<Test>::NotVerified);
+	});
+}
+
+#[test]
+fn only_good_judgements_of_trusted_registrars_verify() {
+	new_test_ext().execute_with(|| {
+		TrustedRegistrars::set(vec![0, 2]);
+		set_judged_identity(Origin::signed(10), 1, Judgement::KnownGood);
+		set_judged_identity(Origin::signed(20), 0, Judgement::LowQuality);
+		set_judged_identity(Origin::signed(40), 0, Judgement::Erroneous);
+		for who in [10, 20, 40] {
+			assert_noop!(Qv::ensure_verified(&who), Error::<Test>::NotVerified);
+		}
+
+		set_judged_identity(Origin::signed(20), 2, Judgement::KnownGood);
+		set_judged_identity(Origin::signed(40), 0, Judgement::Reasonable);
+		assert_ok!(Qv::ensure_verified(&20));
+		assert_ok!(Qv::ensure_verified(&40));
+	});
+}
+
+#[test]
+fn try_cast_launch_votes_self_declared_identity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		set_verified_identity(Origin::signed(30));
+		assert_ok!(initiate_proposal_one(30));
+
+		let launch_voter = Origin::signed(10);
+		assert_ok!(Identity::set_identity(launch_voter.clone(), Box::new(info())));
+		assert_noop!(Qv::cast_launch_votes(launch_voter, 1, 0), Error::<Test>::NotVerified);
+	});
+}
+
+#[test]
+fn try_end_launch_phase_with_self_declared_identity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		set_verified_identity(Origin::signed(30));
+		assert_ok!(initiate_proposal_one(30));
+		set_verified_identity(Origin::signed(31));
+		assert_ok!(Qv::cast_launch_votes(Origin::signed(31), 500, 0));
+
+		// Enough launch votes to place the Decision Deposit
+		let launch_voter = Origin::signed(32);
+		assert_ok!(Identity::set_identity(launch_voter.clone(), Box::new(info())));
+		assert_noop!(Qv::cast_launch_votes(launch_voter, 500, 0), Error::<Test>::NotVerified);
+		assert!(Referenda::referendum_view(0).unwrap().decision_deposit.is_none());
+	});
+}
+
 #[test]
 fn try_reserve_insufficient_balance() {
 	new_test_ext().execute_with(|| {
 		let who = Origin::signed(1);
 
-		assert_ok!(Identity::set_identity(who.clone(), Box::new(info())));
+		set_verified_identity(who.clone());
 
 		assert_noop!(
 			Qv::reserve_an_amount_of_token(who, SMALL_AMOUNT),
@@ -77,7 +176,7 @@ fn try_successful_zero_reserve() {
 	new_test_ext().execute_with(|| {
 		let who = Origin::signed(1);
 
-		assert_ok!(Identity::set_identity(who.clone(), Box::new(info())));
+		set_verified_identity(who.clone());
 
 		assert_ok!(Qv::reserve_an_amount_of_token(who.clone(), 0));
 	});
@@ -89,7 +188,7 @@ fn try_successful_nonzero_reserve() {
 		System::set_block_number(1);
 		let who = Origin::signed(10);
 
-		assert_ok!(Identity::set_identity(who.clone(), Box::new(info())));
+		set_verified_identity(who.clone());
 
 		assert_ok!(Qv::reserve_an_amount_of_token(who.clone(), SMALL_AMOUNT));
 
@@ -105,7 +204,7 @@ fn successive_reserves_until_out_of_funds() {
 	new_test_ext().execute_with(|| {
 		let who = Origin::signed(10);
 
-		assert_ok!(Identity::set_identity(who.clone(), Box::new(info())));
+		set_verified_identity(who.clone());
 
 		assert_ok!(Qv::reserve_an_amount_of_token(who.clone(), 80));
 		assert_ok!(Qv::reserve_an_amount_of_token(who.clone(), 10));
@@ -122,7 +221,7 @@ fn unreserve_then_reserve_again() {
 		System::set_block_number(1);
 		let who = Origin::signed(10);
 
-		assert_ok!(Identity::set_identity(who.clone(), Box::new(info())));
+		set_verified_identity(who.clone());
 
 		assert_ok!(Qv::reserve_an_amount_of_token(who.clone(), 90));
 		assert_noop!(
@@ -149,13 +248,13 @@ fn initiate_referendum_insufficient_balance() {
 		System::set_block_number(1);
 
 		let who = Origin::signed(1);
-		assert_ok!(Identity::set_identity(who.clone(), Box::new(info())));
+		set_verified_identity(who.clone());
 
 		let proposal = BlakeTwo256::hash_of(&1);
 
 		assert_eq!(Balances::free_balance(1), 0);
 		assert_noop!(
-			Qv::initiate_referendum(who, proposal),
+			Qv::initiate_referendum(who, Box::new(RawOrigin::Root.into()), proposal, None),
 			BalancesError::<Test>::InsufficientBalance
 		);
 	});
@@ -167,12 +266,17 @@ fn initiate_referendum_happy_case() {
 		System::set_block_number(1);
 
 		let who = Origin::signed(30);
-		assert_ok!(Identity::set_identity(who.clone(), Box::new(info())));
+		set_verified_identity(who.clone());
 
 		let proposal_hash = BlakeTwo256::hash_of(&1);
 
 		assert_eq!(Balances::free_balance(30), 1000);
-		assert_ok!(Qv::initiate_referendum(who.clone(), proposal_hash));
+		assert_ok!(Qv::initiate_referendum(
+			who.clone(),
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
 		assert_eq!(Balances::free_balance(30), 0);
 
 		System::assert_has_event(Event::Referenda(pallet_referenda::Event::Submitted {
@@ -180,23 +284,183 @@ fn initiate_referendum_happy_case() {
 			track: 0,
 			proposal_hash,
 		}));
+		assert_eq!(
+			last_event(),
+			QvEvent::ReferendumInitiated { index: 0, proposal_hash, initiator: 30 }
+		);
 
 		assert_eq!(ReferendumCount::<Test>::get(), 1);
 	});
 }
 
+#[test]
+fn initiate_referendum_on_the_none_track() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let who = Origin::signed(30);
+		set_verified_identity(who.clone());
+		let proposal_hash = BlakeTwo256::hash_of(&1);
+
+		assert_ok!(Qv::initiate_referendum(
+			who,
+			Box::new(RawOrigin::None.into()),
+			proposal_hash,
+			None
+		));
+		System::assert_has_event(Event::Referenda(pallet_referenda::Event::Submitted {
+			index: 0,
+			track: 1,
+			proposal_hash,
+		}));
+	});
+}
+
+#[test]
+fn initiate_referendum_without_track() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let who = Origin::signed(30);
+		set_verified_identity(who.clone());
+
+		// No track is configured for signed origins
+		assert_noop!(
+			Qv::initiate_referendum(
+				who,
+				Box::new(RawOrigin::Signed(30).into()),
+				BlakeTwo256::hash_of(&1),
+				None
+			),
+			Error::<Test>::NoTrack
+		);
+	});
+}
+
+#[test]
+fn initiate_referendum_with_lifetime_out_of_bounds() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let who = Origin::signed(30);
+		set_verified_identity(who.clone());
+		let proposal_hash = BlakeTwo256::hash_of(&1);
+
+		for lifetime in [MIN_REFERENDUM_LIFETIME - 1, MAX_REFERENDUM_LIFETIME + 1] {
+			assert_noop!(
+				Qv::initiate_referendum(
+					who.clone(),
+					Box::new(RawOrigin::Root.into()),
+					proposal_hash,
+					Some(lifetime)
+				),
+				Error::<Test>::LifetimeOutOfBounds
+			);
+		}
+	});
+}
+
+#[test]
+fn referendum_lifetime_sets_the_deadline() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let who = Origin::signed(30);
+		set_verified_identity(who.clone());
+		assert_ok!(Qv::initiate_referendum(
+			who.clone(),
+			Box::new(RawOrigin::Root.into()),
+			BlakeTwo256::hash_of(&1),
+			None
+		));
+		assert_ok!(Qv::initiate_referendum(
+			who,
+			Box::new(RawOrigin::Root.into()),
+			BlakeTwo256::hash_of(&2),
+			Some(100)
+		));
+
+		let deadline = |index| Referenda::referendum_view(index).unwrap().ongoing.unwrap().deadline;
+		assert_eq!(deadline(0), Some(1 + REFERENDUM_LIFETIME));
+		assert_eq!(deadline(1), Some(101));
+	});
+}
+
+#[test]
+fn referendum_times_out_at_its_deadline() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		let referendum_initiator = Origin::signed(30);
+		set_verified_identity(referendum_initiator.clone());
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			BlakeTwo256::hash_of(&1),
+			Some(100)
+		));
+
+		let voter = Origin::signed(20);
+		set_verified_identity(voter.clone());
+		assert_ok!(Qv::cast_launch_votes(voter, 10, 0));
+		assert_eq!(Balances::free_balance(20), 0);
+
+		run_to(100);
+		assert!(Referenda::is_ongoing(0));
+		run_to(101);
+		assert_eq!(Referenda::referendum_view(0).unwrap().phase, ReferendumPhase::TimedOut);
+		// The launch votes were refunded when the referendum timed out.
+		assert_eq!(Balances::free_balance(20), 100);
+	});
+}
+
+#[test]
+fn initiated_referenda_get_consecutive_indices() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		for (initiator, proposal) in [(30, 1), (31, 2)] {
+			let who = Origin::signed(initiator);
+			set_verified_identity(who.clone());
+			assert_ok!(Qv::initiate_referendum(
+				who,
+				Box::new(RawOrigin::Root.into()),
+				BlakeTwo256::hash_of(&proposal),
+				None
+			));
+		}
+
+		assert_eq!(
+			last_event(),
+			QvEvent::ReferendumInitiated {
+				index: 1,
+				proposal_hash: BlakeTwo256::hash_of(&2),
+				initiator: 31
+			}
+		);
+		assert!(Qv::depositors(0, 30).is_some());
+		assert!(Qv::depositors(1, 31).is_some());
+		assert_eq!(Qv::launch_totals(1), LaunchTally { backers: 1, votes: 0 });
+	});
+}
+
 #[test]
 fn cast_single_launch_vote() {
 	new_test_ext().execute_with(|| {
 		// Events are not populated in the genesis block
 		System::set_block_number(1);
 		let referendum_initiator = Origin::signed(30);
-		assert_ok!(Identity::set_identity(referendum_initiator.clone(), Box::new(info())));
+		set_verified_identity(referendum_initiator.clone());
 		let proposal_hash = BlakeTwo256::hash_of(&1);
-		assert_ok!(Qv::initiate_referendum(referendum_initiator, proposal_hash));
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
 
 		let launch_voter = Origin::signed(20);
-		assert_ok!(Identity::set_identity(launch_voter.clone(), Box::new(info())));
+		set_verified_identity(launch_voter.clone());
 		assert_ok!(Qv::cast_launch_votes(launch_voter, 1, 0));
 
 		assert_eq!(last_event(), QvEvent::LaunchVotesCast { number_of_votes: 1, index: 0 });
@@ -207,12 +471,17 @@ fn cast_single_launch_vote() {
 fn cast_zero_votes() {
 	new_test_ext().execute_with(|| {
 		let referendum_initiator = Origin::signed(30);
-		assert_ok!(Identity::set_identity(referendum_initiator.clone(), Box::new(info())));
+		set_verified_identity(referendum_initiator.clone());
 		let proposal_hash = BlakeTwo256::hash_of(&1);
-		assert_ok!(Qv::initiate_referendum(referendum_initiator, proposal_hash));
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
 
 		let launch_voter = Origin::signed(20);
-		assert_ok!(Identity::set_identity(launch_voter.clone(), Box::new(info())));
+		set_verified_identity(launch_voter.clone());
 		assert_eq!(Balances::free_balance(20), 100);
 		assert_noop!(Qv::cast_launch_votes(launch_voter, 0, 0), Error::<Test>::ZeroVote);
 		assert_eq!(Balances::free_balance(20), 100);
@@ -223,12 +492,17 @@ fn cast_zero_votes() {
 fn cast_more_launch_votes_than_allowed() {
 	new_test_ext().execute_with(|| {
 		let referendum_initiator = Origin::signed(30);
-		assert_ok!(Identity::set_identity(referendum_initiator.clone(), Box::new(info())));
+		set_verified_identity(referendum_initiator.clone());
 		let proposal_hash = BlakeTwo256::hash_of(&1);
-		assert_ok!(Qv::initiate_referendum(referendum_initiator, proposal_hash));
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
 
 		let launch_voter = Origin::signed(20);
-		assert_ok!(Identity::set_identity(launch_voter.clone(), Box::new(info())));
+		set_verified_identity(launch_voter.clone());
 		assert_eq!(Balances::free_balance(20), 100);
 		assert_noop!(
 			Qv::cast_launch_votes(launch_voter, 11, 0),
@@ -242,9 +516,14 @@ fn cast_more_launch_votes_than_allowed() {
 fn try_cast_vote_no_identity() {
 	new_test_ext().execute_with(|| {
 		let referendum_initiator = Origin::signed(30);
-		assert_ok!(Identity::set_identity(referendum_initiator.clone(), Box::new(info())));
+		set_verified_identity(referendum_initiator.clone());
 		let proposal_hash = BlakeTwo256::hash_of(&1);
-		assert_ok!(Qv::initiate_referendum(referendum_initiator, proposal_hash));
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
 
 		let launch_voter = Origin::signed(20);
 		assert_noop!(Qv::cast_launch_votes(launch_voter, 1, 0), Error::<Test>::NoIdentity);
@@ -255,7 +534,7 @@ fn try_cast_vote_no_identity() {
 fn try_cast_vote_not_ongoing() {
 	new_test_ext().execute_with(|| {
 		let launch_voter = Origin::signed(20);
-		assert_ok!(Identity::set_identity(launch_voter.clone(), Box::new(info())));
+		set_verified_identity(launch_voter.clone());
 		assert_noop!(Qv::cast_launch_votes(launch_voter, 1, 0), ReferendaError::<Test>::NotOngoing);
 	});
 }
@@ -264,15 +543,149 @@ fn try_cast_vote_not_ongoing() {
 fn cast_launch_votes_twice() {
 	new_test_ext().execute_with(|| {
 		let referendum_initiator = Origin::signed(30);
-		assert_ok!(Identity::set_identity(referendum_initiator.clone(), Box::new(info())));
+		set_verified_identity(referendum_initiator.clone());
 		let proposal_hash = BlakeTwo256::hash_of(&1);
-		assert_ok!(Qv::initiate_referendum(referendum_initiator, proposal_hash));
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
 
 		let launch_voter = Origin::signed(31);
-		assert_ok!(Identity::set_identity(launch_voter.clone(), Box::new(info())));
+		set_verified_identity(launch_voter.clone());
+
+		assert_ok!(Qv::cast_launch_votes(launch_voter.clone(), 3, 0));
+		assert_eq!(Balances::reserved_balance(31), 10 + 9);
+
+		// Topping up from 3 to 5 votes only costs the difference
+		assert_ok!(Qv::cast_launch_votes(launch_voter, 2, 0));
+		assert_eq!(last_event(), QvEvent::LaunchVotesCast { number_of_votes: 2, index: 0 });
+		assert_eq!(Balances::reserved_balance(31), 10 + 25);
+		assert_eq!(
+			Qv::depositors(0, 31),
+			Some(BackerRecord { votes: 5, cost: 25, decision_deposit: false })
+		);
+		assert_eq!(Qv::launch_totals(0), LaunchTally { backers: 2, votes: 5 });
+	});
+}
+
+#[test]
+fn top_up_that_ends_the_launch_phase_places_the_whole_cost_as_decision_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let referendum_initiator = Origin::signed(30);
+		set_verified_identity(referendum_initiator.clone());
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			BlakeTwo256::hash_of(&1),
+			None
+		));
+		for (backer, votes) in [(31, 500), (32, 400)] {
+			let launch_voter = Origin::signed(backer);
+			set_verified_identity(launch_voter.clone());
+			assert_ok!(Qv::cast_launch_votes(launch_voter, votes, 0));
+		}
+		assert_eq!(Balances::reserved_balance(32), 10 + 160_000);
+
+		assert_ok!(Qv::cast_launch_votes(Origin::signed(32), 100, 0));
+		System::assert_has_event(Event::Referenda(
+			pallet_referenda::Event::DecisionDepositPlaced { index: 0, who: 32, amount: 250_000 },
+		));
+		assert_eq!(Balances::reserved_balance(32), 10 + 250_000);
+		assert_eq!(
+			Qv::depositors(0, 32),
+			Some(BackerRecord { votes: 500, cost: 250_000, decision_deposit: true })
+		);
+
+		// Everything gets refunded when the referendum is cancelled
+		assert_ok!(Referenda::cancel(RawOrigin::Root.into(), 0));
+		assert_eq!(Balances::reserved_balance(31), 10);
+		assert_eq!(Balances::reserved_balance(32), 10);
+	});
+}
+
+#[test]
+fn reduce_launch_votes_unreserves_the_difference() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+
+		// Going from 7 to 3 votes unreserves 49 - 9
+		assert_ok!(Qv::reduce_launch_votes(Origin::signed(40), 4, 0));
+		assert_eq!(last_event(), QvEvent::LaunchVotesReduced { number_of_votes: 4, index: 0 });
+		assert_eq!(Balances::reserved_balance(40), 10 + 9);
+		assert_eq!(
+			Qv::depositors(0, 40),
+			Some(BackerRecord { votes: 3, cost: 9, decision_deposit: false })
+		);
+		assert_eq!(Qv::launch_totals(0), LaunchTally { backers: 4, votes: 3 + 5 + 3 });
+	});
+}
 
-		assert_ok!(Qv::cast_launch_votes(launch_voter.clone(), 5, 0));
-		assert_noop!(Qv::cast_launch_votes(launch_voter, 1, 0), Error::<Test>::AlreadyVoted);
+#[test]
+fn withdraw_launch_votes_unreserves_the_whole_cost() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+
+		assert_ok!(Qv::withdraw_launch_votes(Origin::signed(20), 0));
+		assert_eq!(last_event(), QvEvent::LaunchVotesWithdrawn { number_of_votes: 5, index: 0 });
+		assert_eq!(Balances::reserved_balance(20), 10);
+		assert_eq!(Qv::depositors(0, 20), None);
+		assert_eq!(Qv::launch_totals(0), LaunchTally { backers: 3, votes: 3 + 7 });
+
+		// Withdrawn backers may back the referendum again
+		assert_ok!(Qv::cast_launch_votes(Origin::signed(20), 2, 0));
+		assert_eq!(Balances::reserved_balance(20), 10 + 4);
+	});
+}
+
+#[test]
+fn try_withdraw_launch_votes() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+
+		assert_noop!(Qv::withdraw_launch_votes(Origin::signed(41), 0), Error::<Test>::NotBacker);
+		// The initiator can not withdraw its submission deposit
+		assert_noop!(Qv::withdraw_launch_votes(Origin::signed(30), 0), Error::<Test>::NotBacker);
+
+		for backer in [31, 32] {
+			let launch_voter = Origin::signed(backer);
+			set_verified_identity(launch_voter.clone());
+			assert_ok!(Qv::cast_launch_votes(launch_voter, 495, 0));
+		}
+		assert_noop!(
+			Qv::withdraw_launch_votes(Origin::signed(40), 0),
+			Error::<Test>::LaunchPhaseOver
+		);
+	});
+}
+
+#[test]
+fn try_reduce_launch_votes() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+
+		assert_noop!(Qv::reduce_launch_votes(Origin::signed(40), 0, 0), Error::<Test>::ZeroVote);
+		assert_noop!(Qv::reduce_launch_votes(Origin::signed(41), 1, 0), Error::<Test>::NotBacker);
+		assert_noop!(
+			Qv::reduce_launch_votes(Origin::signed(40), 7, 0),
+			Error::<Test>::NotEnoughVotes
+		);
+		assert_noop!(
+			Qv::reduce_launch_votes(Origin::signed(30), 1, 0),
+			Error::<Test>::NotEnoughVotes
+		);
+
+		for backer in [31, 32] {
+			let launch_voter = Origin::signed(backer);
+			set_verified_identity(launch_voter.clone());
+			assert_ok!(Qv::cast_launch_votes(launch_voter, 495, 0));
+		}
+		assert_noop!(
+			Qv::reduce_launch_votes(Origin::signed(40), 1, 0),
+			Error::<Test>::LaunchPhaseOver
+		);
 	});
 }
 
@@ -280,9 +693,14 @@ fn cast_launch_votes_twice() {
 fn initiator_tries_to_cast_launch_votes() {
 	new_test_ext().execute_with(|| {
 		let referendum_initiator = Origin::signed(30);
-		assert_ok!(Identity::set_identity(referendum_initiator.clone(), Box::new(info())));
+		set_verified_identity(referendum_initiator.clone());
 		let proposal_hash = BlakeTwo256::hash_of(&1);
-		assert_ok!(Qv::initiate_referendum(referendum_initiator.clone(), proposal_hash));
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator.clone(),
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
 
 		assert_noop!(
 			Qv::cast_launch_votes(referendum_initiator, 1, 0),
@@ -296,14 +714,19 @@ fn cast_launch_votes_until_full_deposit_triggered_and_beyond() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 		let referendum_initiator = Origin::signed(30);
-		assert_ok!(Identity::set_identity(referendum_initiator.clone(), Box::new(info())));
+		set_verified_identity(referendum_initiator.clone());
 		let proposal_hash = BlakeTwo256::hash_of(&1);
-		assert_ok!(Qv::initiate_referendum(referendum_initiator, proposal_hash));
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
 
 		let launch_voter_0 = Origin::signed(31);
-		assert_ok!(Identity::set_identity(launch_voter_0.clone(), Box::new(info())));
+		set_verified_identity(launch_voter_0.clone());
 		let launch_voter_1 = Origin::signed(32);
-		assert_ok!(Identity::set_identity(launch_voter_1.clone(), Box::new(info())));
+		set_verified_identity(launch_voter_1.clone());
 
 		assert_eq!(Balances::free_balance(31), 250_000);
 		assert_ok!(Qv::cast_launch_votes(launch_voter_0, 500, 0)); // Cast 500 votes
@@ -317,7 +740,7 @@ fn cast_launch_votes_until_full_deposit_triggered_and_beyond() {
 		));
 
 		let launch_voter_2 = Origin::signed(20);
-		assert_ok!(Identity::set_identity(launch_voter_2.clone(), Box::new(info())));
+		set_verified_identity(launch_voter_2.clone());
 
 		assert_noop!(
 			Qv::cast_launch_votes(launch_voter_2, 1, 0),
@@ -327,19 +750,24 @@ fn cast_launch_votes_until_full_deposit_triggered_and_beyond() {
 }
 
 #[test]
-fn launch_phase_can_get_cancelled_by_root_leads_to_slash() {
+fn launch_phase_can_get_cancelled_by_root_leads_to_refund() {
 	new_test_ext().execute_with(|| {
 		// Events are not populated in the genesis block
 		System::set_block_number(1);
 		let initiator_num = 30;
 		let referendum_initiator = Origin::signed(initiator_num);
-		assert_ok!(Identity::set_identity(referendum_initiator.clone(), Box::new(info())));
+		set_verified_identity(referendum_initiator.clone());
 		let proposal_hash = BlakeTwo256::hash_of(&1);
-		assert_ok!(Qv::initiate_referendum(referendum_initiator, proposal_hash));
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
 
 		let voter_num = 20;
 		let launch_voter = Origin::signed(voter_num);
-		assert_ok!(Identity::set_identity(launch_voter.clone(), Box::new(info())));
+		set_verified_identity(launch_voter.clone());
 		assert_ok!(Qv::cast_launch_votes(launch_voter, 10, 0));
 
 		// Both actors have zero balances
@@ -353,12 +781,1863 @@ fn launch_phase_can_get_cancelled_by_root_leads_to_slash() {
 
 		assert_ok!(Referenda::cancel(RawOrigin::Root.into(), 0));
 
-		assert_err!(
-			Qv::refund_launch_votes(RawOrigin::Root.into(), 0),
-			ReferendaError::<Test>::NoDeposit
-		);
-
+		// The launch voter got refunded automatically, the initiator's submission deposit is
+		// still held by pallet_referenda
 		assert_eq!(Balances::free_balance(initiator_num), 0);
-		assert_eq!(Balances::free_balance(voter_num), 0);
+		assert_eq!(Balances::free_balance(voter_num), 100);
+		assert_eq!(last_event(), QvEvent::AmountUnreserved(100));
+
+		// Refunding again does nothing
+		assert_ok!(Qv::refund_launch_votes(RawOrigin::Root.into(), 0));
+		assert_eq!(Balances::free_balance(voter_num), 100);
+	});
+}
+
+/// Initiate referendum 0 at block 1 and cast enough launch votes to end its launch phase.
+fn launch_referendum() {
+	System::set_block_number(1);
+	let referendum_initiator = Origin::signed(30);
+	set_verified_identity(referendum_initiator.clone());
+	let proposal_hash = BlakeTwo256::hash_of(&1);
+	assert_ok!(Qv::initiate_referendum(
+		referendum_initiator,
+		Box::new(RawOrigin::Root.into()),
+		proposal_hash,
+		None
+	));
+
+	for backer in [31, 32] {
+		let launch_voter = Origin::signed(backer);
+		set_verified_identity(launch_voter.clone());
+		assert_ok!(Qv::cast_launch_votes(launch_voter, 500, 0));
+	}
+}
+
+const DECISION_STARTS: u64 = 1 + ONE_MONTH;
+
+#[test]
+fn try_vote_during_launch_phase() {
+	new_test_ext().execute_with(|| {
+		let referendum_initiator = Origin::signed(30);
+		set_verified_identity(referendum_initiator.clone());
+		let proposal_hash = BlakeTwo256::hash_of(&1);
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::Root.into()),
+			proposal_hash,
+			None
+		));
+
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		assert_noop!(Qv::vote_aye(voter, 1, 0), Error::<Test>::StillInLaunchPhase);
+	});
+}
+
+#[test]
+fn try_vote_twice() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		assert_ok!(Qv::vote_aye(voter.clone(), 1, 0));
+		assert_noop!(Qv::vote_nay(voter, 1, 0), Error::<Test>::AlreadyVoted);
+	});
+}
+
+#[test]
+fn quadratic_ayes_confirm_referendum() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+		System::assert_has_event(Event::Referenda(pallet_referenda::Event::DecisionStarted {
+			index: 0,
+			track: 0,
+			proposal_hash: BlakeTwo256::hash_of(&1),
+			tally: QuadraticTally { ayes: 0, nays: 0, supporters: 0, electorate: 1 },
+		}));
+
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		assert_eq!(Balances::free_balance(40), 10_000);
+		assert_ok!(Qv::vote_aye(voter, 100, 0));
+		assert_eq!(Balances::free_balance(40), 0);
+		assert_eq!(last_event(), QvEvent::VotesCast { aye: true, number_of_votes: 100, index: 0 });
+
+		run_to(DECISION_STARTS + 1);
+		System::assert_has_event(Event::Referenda(pallet_referenda::Event::ConfirmStarted {
+			index: 0,
+		}));
+
+		run_to(DECISION_STARTS + 2);
+		System::assert_has_event(Event::Referenda(pallet_referenda::Event::Confirmed {
+			index: 0,
+			tally: QuadraticTally { ayes: 100, nays: 0, supporters: 1, electorate: 1 },
+		}));
+	});
+}
+
+#[test]
+fn quadratic_nays_reject_referendum_and_votes_get_unreserved() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+
+		let voter = Origin::signed(41);
+		set_verified_identity(voter.clone());
+		assert_ok!(Qv::vote_nay(voter.clone(), 10, 0));
+		assert_eq!(Balances::free_balance(41), 9_900);
+		assert_noop!(Qv::remove_vote(voter.clone(), 0), Error::<Test>::StillOngoing);
+
+		run_to(DECISION_STARTS + ONE_MONTH);
+		System::assert_has_event(Event::Referenda(pallet_referenda::Event::Rejected {
+			index: 0,
+			tally: QuadraticTally { ayes: 0, nays: 10, supporters: 0, electorate: 1 },
+		}));
+
+		assert_ok!(Qv::remove_vote(voter.clone(), 0));
+		assert_eq!(Balances::free_balance(41), 10_000);
+		assert_noop!(Qv::remove_vote(voter, 0), Error::<Test>::NotVoted);
+	});
+}
+
+#[test]
+fn decision_deposit_gets_refunded_when_referendum_concludes() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		assert_eq!(Balances::free_balance(32), 0);
+
+		// Nobody votes, so the referendum gets rejected at the end of its deciding phase
+		run_to(DECISION_STARTS + ONE_MONTH);
+		System::assert_has_event(Event::Referenda(pallet_referenda::Event::Rejected {
+			index: 0,
+			tally: QuadraticTally { ayes: 0, nays: 0, supporters: 0, electorate: 1 },
+		}));
+		System::assert_has_event(Event::Referenda(
+			pallet_referenda::Event::DecisionDepositRefunded { index: 0, who: 32, amount: 250_000 },
+		));
+		assert_eq!(Balances::free_balance(32), 250_000);
+	});
+}
+
+/// Initiate referendum 0 and let accounts 10, 20 and 40 back it with 3, 5 and 7 launch votes.
+fn back_referendum_with_three_backers() {
+	System::set_block_number(1);
+	let referendum_initiator = Origin::signed(30);
+	set_verified_identity(referendum_initiator.clone());
+	let proposal_hash = BlakeTwo256::hash_of(&1);
+	assert_ok!(Qv::initiate_referendum(
+		referendum_initiator,
+		Box::new(RawOrigin::Root.into()),
+		proposal_hash,
+		None
+	));
+
+	for (backer, votes) in [(10, 3), (20, 5), (40, 7)] {
+		let launch_voter = Origin::signed(backer);
+		set_verified_identity(launch_voter.clone());
+		assert_ok!(Qv::cast_launch_votes(launch_voter, votes, 0));
+	}
+	assert_eq!(Balances::reserved_balance(10), 10 + 9);
+	assert_eq!(Balances::reserved_balance(20), 10 + 25);
+	assert_eq!(Balances::reserved_balance(40), 10 + 49);
+}
+
+#[test]
+fn launch_totals_count_every_backer() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+
+		assert_eq!(Qv::launch_totals(0), LaunchTally { backers: 4, votes: 3 + 5 + 7 });
+		assert_eq!(
+			Qv::depositors(0, 20),
+			Some(BackerRecord { votes: 5, cost: 25, decision_deposit: false })
+		);
+	});
+}
+
+#[test]
+fn try_back_referendum_with_too_many_backers() {
+	new_test_ext().execute_with(|| {
+		MaxBackers::set(4);
+		back_referendum_with_three_backers();
+
+		let launch_voter = Origin::signed(41);
+		set_verified_identity(launch_voter.clone());
+		assert_noop!(Qv::cast_launch_votes(launch_voter, 1, 0), Error::<Test>::TooManyBackers);
+	});
+}
+
+#[test]
+fn every_launch_backer_gets_refunded_its_quadratic_cost() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+
+		assert_ok!(Referenda::cancel(RawOrigin::Root.into(), 0));
+
+		// Only the identity deposits remain reserved
+		assert_eq!(Balances::reserved_balance(10), 10);
+		assert_eq!(Balances::reserved_balance(20), 10);
+		assert_eq!(Balances::reserved_balance(40), 10);
+		assert_eq!(Balances::free_balance(10), 90);
+		assert_eq!(Balances::free_balance(20), 100);
+		assert_eq!(Balances::free_balance(40), 10_000);
+		assert_eq!(Depositors::<Test>::iter_prefix(0).count(), 0);
+		assert_eq!(Qv::launch_totals(0), LaunchTally::default());
+	});
+}
+
+#[test]
+fn launch_backers_and_decision_depositor_get_refunded_after_rejection() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+		for backer in [31, 32] {
+			let launch_voter = Origin::signed(backer);
+			set_verified_identity(launch_voter.clone());
+			assert_ok!(Qv::cast_launch_votes(launch_voter, 495, 0));
+		}
+		System::assert_has_event(Event::Referenda(
+			pallet_referenda::Event::DecisionDepositPlaced { index: 0, who: 32, amount: 245_025 },
+		));
+
+		run_to(DECISION_STARTS + ONE_MONTH);
+		System::assert_has_event(Event::Referenda(pallet_referenda::Event::Rejected {
+			index: 0,
+			tally: QuadraticTally { ayes: 0, nays: 0, supporters: 0, electorate: 1 },
+		}));
+
+		assert_eq!(Balances::free_balance(10), 90);
+		assert_eq!(Balances::free_balance(20), 100);
+		assert_eq!(Balances::free_balance(40), 10_000);
+		assert_eq!(Balances::free_balance(31), 250_000);
+		assert_eq!(Balances::free_balance(32), 250_000);
+	});
+}
+
+#[test]
+fn every_launch_backer_gets_slashed_when_referendum_is_killed() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+
+		assert_ok!(Referenda::kill(RawOrigin::Root.into(), 0));
+		assert_eq!(last_event(), QvEvent::AmountSlashed(49));
+
+		assert_eq!(Balances::reserved_balance(10), 10);
+		assert_eq!(Balances::reserved_balance(20), 10);
+		assert_eq!(Balances::reserved_balance(40), 10);
+		assert_eq!(Balances::free_balance(10), 81);
+		assert_eq!(Balances::free_balance(20), 75);
+		assert_eq!(Balances::free_balance(40), 9_951);
+	});
+}
+
+#[test]
+fn concluding_a_referendum_is_weighed_with_settling_its_launch_backers() {
+	new_test_ext().execute_with(|| {
+		let settle = <Qv as ReferendumHooks<QuadraticTally>>::max_conclusion_weight();
+		let cancel = pallet_referenda::Call::<Test>::cancel { index: 0 }.get_dispatch_info();
+		let kill = pallet_referenda::Call::<Test>::kill { index: 0 }.get_dispatch_info();
+		assert!(cancel.weight >= settle);
+		assert!(kill.weight >= settle);
+
+		// The work grows with the number of backers a referendum may have
+		MaxBackers::set(200);
+		assert!(<Qv as ReferendumHooks<QuadraticTally>>::max_conclusion_weight() > settle);
+	});
+}
+
+/// Initiate a referendum on proposal 1 from `initiator`, who must have an identity.
+fn initiate_proposal_one(initiator: u64) -> sp_runtime::DispatchResult {
+	Qv::initiate_referendum(
+		Origin::signed(initiator),
+		Box::new(RawOrigin::Root.into()),
+		BlakeTwo256::hash_of(&1),
+		None,
+	)
+}
+
+#[test]
+fn try_initiate_ongoing_proposal_again() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for initiator in [30, 31] {
+			set_verified_identity(Origin::signed(initiator));
+		}
+
+		assert_ok!(initiate_proposal_one(30));
+		assert_eq!(Qv::proposal_of(BlakeTwo256::hash_of(&1)), Some(0));
+		assert_noop!(initiate_proposal_one(31), Error::<Test>::DuplicateProposal);
+	});
+}
+
+#[test]
+fn referenda_can_only_be_submitted_through_qv() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_noop!(
+			Referenda::submit(
+				Origin::signed(30),
+				Box::new(RawOrigin::Root.into()),
+				BlakeTwo256::hash_of(&1),
+				DispatchTime::After(0),
+			),
+			DispatchError::BadOrigin
+		);
+
+		set_verified_identity(Origin::signed(30));
+		assert_ok!(initiate_proposal_one(30));
+		assert_eq!(ReferendumCount::<Test>::get(), 1);
+	});
+}
+
+#[test]
+fn cancelled_proposal_can_be_initiated_again_after_cooldown() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for initiator in [30, 31] {
+			set_verified_identity(Origin::signed(initiator));
+		}
+		assert_ok!(initiate_proposal_one(30));
+		assert_ok!(Referenda::cancel(RawOrigin::Root.into(), 0));
+
+		System::set_block_number(RESUBMISSION_COOLDOWN);
+		assert_noop!(initiate_proposal_one(31), Error::<Test>::ResubmissionCoolingDown);
+
+		System::set_block_number(1 + RESUBMISSION_COOLDOWN);
+		assert_ok!(initiate_proposal_one(31));
+		assert_eq!(Qv::proposal_of(BlakeTwo256::hash_of(&1)), Some(1));
+	});
+}
+
+#[test]
+fn cancelled_proposal_can_not_be_initiated_again_without_resubmission() {
+	new_test_ext().execute_with(|| {
+		AllowResubmission::set(false);
+		System::set_block_number(1);
+		for initiator in [30, 31] {
+			set_verified_identity(Origin::signed(initiator));
+		}
+		assert_ok!(initiate_proposal_one(30));
+		assert_ok!(Referenda::cancel(RawOrigin::Root.into(), 0));
+
+		System::set_block_number(1 + RESUBMISSION_COOLDOWN);
+		assert_noop!(initiate_proposal_one(31), Error::<Test>::DuplicateProposal);
+	});
+}
+
+#[test]
+fn approved_proposal_can_not_be_initiated_again() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		assert_ok!(Qv::vote_aye(voter, 100, 0));
+		run_to(DECISION_STARTS + 2);
+		assert_eq!(Referenda::referendum_view(0).unwrap().phase, ReferendumPhase::Approved);
+
+		System::set_block_number(DECISION_STARTS + 2 + RESUBMISSION_COOLDOWN);
+		set_verified_identity(Origin::signed(41));
+		assert_noop!(initiate_proposal_one(41), Error::<Test>::DuplicateProposal);
+	});
+}
+
+#[test]
+fn quadratic_cost_is_the_square_of_every_accepted_number_of_votes() {
+	// Every number of votes whose square fits in a u32
+	for votes in 0..=u16::MAX as u32 {
+		assert_eq!(quadratic_cost::<u32>(votes), Some(votes * votes));
+	}
+	// Every other number of votes overflows a u32
+	for votes in (u16::MAX as u32 + 1..=u32::MAX).step_by(9_973).chain([u32::MAX]) {
+		assert_eq!(quadratic_cost::<u32>(votes), None);
+	}
+	// A u64 fits the square of any number of votes
+	for votes in (0..=u32::MAX).step_by(9_973).chain([u32::MAX]) {
+		assert_eq!(quadratic_cost::<u64>(votes), Some(votes as u64 * votes as u64));
+	}
+}
+
+#[test]
+fn try_cast_more_votes_than_allowed_per_call() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		assert_noop!(
+			Qv::cast_launch_votes(voter.clone(), MAX_VOTES_PER_CALL + 1, 0),
+			Error::<Test>::TooManyVotes
+		);
+
+		run_to(DECISION_STARTS);
+		assert_noop!(
+			Qv::vote_aye(voter.clone(), MAX_VOTES_PER_CALL + 1, 0),
+			Error::<Test>::TooManyVotes
+		);
+		assert_noop!(Qv::vote_nay(voter, MAX_VOTES_PER_CALL + 1, 0), Error::<Test>::TooManyVotes);
+	});
+}
+
+#[test]
+fn vote_cost_curves() {
+	type ThreeHalves = RationalPower<ConstU32<3>, ConstU32<2>>;
+	type Squared = RationalPower<ConstU32<2>, ConstU32<1>>;
+	let cost = |curve: fn(u32, u8) -> Option<u64>| (0..=5).map(|votes| curve(votes, 0).unwrap());
+
+	assert!(cost(<Linear as VoteCostCurve<u64, u8>>::cost).eq([0, 1, 2, 3, 4, 5]));
+	assert!(cost(<Quadratic as VoteCostCurve<u64, u8>>::cost).eq([0, 1, 4, 9, 16, 25]));
+	assert!(cost(<Cubic as VoteCostCurve<u64, u8>>::cost).eq([0, 1, 8, 27, 64, 125]));
+	// 2^1.5 = 2.83 and 5^1.5 = 11.18 get rounded down
+	assert!(cost(<ThreeHalves as VoteCostCurve<u64, u8>>::cost).eq([0, 1, 2, 5, 8, 11]));
+
+	for votes in (0..=u32::MAX).step_by(9_973).chain([u32::MAX]) {
+		assert_eq!(
+			<Squared as VoteCostCurve<u64, u8>>::cost(votes, 0),
+			<Quadratic as VoteCostCurve<u64, u8>>::cost(votes, 0)
+		);
+	}
+	assert_eq!(<Cubic as VoteCostCurve<u64, u8>>::cost(u32::MAX, 0), None);
+}
+
+#[test]
+fn launch_votes_on_the_none_track_are_priced_linearly() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let referendum_initiator = Origin::signed(30);
+		set_verified_identity(referendum_initiator.clone());
+		assert_ok!(Qv::initiate_referendum(
+			referendum_initiator,
+			Box::new(RawOrigin::None.into()),
+			BlakeTwo256::hash_of(&1),
+			None
+		));
+
+		let launch_voter = Origin::signed(20);
+		set_verified_identity(launch_voter.clone());
+		assert_ok!(Qv::cast_launch_votes(launch_voter.clone(), 10, 0));
+		assert_eq!(Balances::reserved_balance(20), 10 + 10);
+		assert_ok!(Qv::cast_launch_votes(launch_voter, 5, 0));
+		assert_eq!(Balances::reserved_balance(20), 10 + 15);
+	});
+}
+
+/// Initiate referendum 0 on the "none" track, where votes are priced linearly.
+fn initiate_on_the_none_track() {
+	System::set_block_number(1);
+	let referendum_initiator = Origin::signed(30);
+	set_verified_identity(referendum_initiator.clone());
+	assert_ok!(Qv::initiate_referendum(
+		referendum_initiator,
+		Box::new(RawOrigin::None.into()),
+		BlakeTwo256::hash_of(&1),
+		None
+	));
+}
+
+#[test]
+fn launch_votes_spend_voice_credits_instead_of_reserving_funds() {
+	new_test_ext().execute_with(|| {
+		Pricing::set(VotePricing::VoiceCredits);
+		System::set_block_number(1);
+		set_verified_identity(Origin::signed(30));
+		assert_ok!(initiate_proposal_one(30));
+
+		let launch_voter = Origin::signed(10);
+		set_verified_identity(launch_voter.clone());
+		assert_eq!(Qv::voice_credits(&10), 1_000);
+		assert_ok!(Qv::cast_launch_votes(launch_voter.clone(), 3, 0));
+		System::assert_has_event(Event::Qv(QvEvent::VoiceCreditsSpent(9)));
+		assert_eq!(Qv::voice_credits(&10), 991);
+		// Only the identity deposit is reserved
+		assert_eq!(Balances::reserved_balance(10), 10);
+
+		assert_ok!(Qv::reduce_launch_votes(launch_voter.clone(), 1, 0));
+		assert_eq!(Qv::voice_credits(&10), 996);
+		assert_noop!(
+			Qv::cast_launch_votes(launch_voter, 30, 0),
+			Error::<Test>::InsufficientVoiceCredits
+		);
+
+		assert_ok!(Referenda::cancel(RawOrigin::Root.into(), 0));
+		assert_eq!(last_event(), QvEvent::VoiceCreditsRefunded(4));
+		assert_eq!(Qv::voice_credits(&10), 1_000);
+		assert_eq!(Balances::reserved_balance(10), 10);
+	});
+}
+
+#[test]
+fn voice_credits_pay_for_the_decision_deposit_and_deciding_votes() {
+	new_test_ext().execute_with(|| {
+		Pricing::set(VotePricing::VoiceCredits);
+		initiate_on_the_none_track();
+
+		let launch_voter = Origin::signed(20);
+		set_verified_identity(launch_voter.clone());
+		assert_ok!(Qv::cast_launch_votes(launch_voter, 1_000, 0));
+		System::assert_has_event(Event::Referenda(
+			pallet_referenda::Event::DecisionDepositPlaced { index: 0, who: 20, amount: 0 },
+		));
+		assert_eq!(Qv::voice_credits(&20), 0);
+		assert_eq!(Balances::reserved_balance(20), 10);
+
+		run_to(DECISION_STARTS);
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		assert_ok!(Qv::vote_nay(voter.clone(), 10, 0));
+		assert_eq!(Qv::voice_credits(&40), 990);
+		assert_eq!(Balances::reserved_balance(40), 10);
+
+		run_to(DECISION_STARTS + ONE_MONTH);
+		System::assert_has_event(Event::Referenda(pallet_referenda::Event::Rejected {
+			index: 0,
+			tally: QuadraticTally { ayes: 0, nays: 10, supporters: 0, electorate: 1 },
+		}));
+		assert_eq!(Qv::voice_credits(&20), 1_000);
+
+		assert_ok!(Qv::remove_vote(voter, 0));
+		assert_eq!(Qv::voice_credits(&40), 1_000);
+	});
+}
+
+#[test]
+fn voice_credits_of_launch_votes_are_burned_when_referendum_is_killed() {
+	new_test_ext().execute_with(|| {
+		Pricing::set(VotePricing::VoiceCredits);
+		initiate_on_the_none_track();
+
+		let launch_voter = Origin::signed(10);
+		set_verified_identity(launch_voter.clone());
+		assert_ok!(Qv::cast_launch_votes(launch_voter, 30, 0));
+
+		assert_ok!(Referenda::kill(RawOrigin::Root.into(), 0));
+		assert_eq!(last_event(), QvEvent::VoiceCreditsBurned(30));
+		assert_eq!(Qv::voice_credits(&10), 970);
+		assert_eq!(Balances::free_balance(10), 90);
+	});
+}
+
+#[test]
+fn epochs_start_every_epoch_length_blocks() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		EpochLength::set(10);
+		// Epochs only matter when votes are paid in voice credits
+		Qv::on_initialize(10);
+		assert_eq!(Qv::current_epoch(), 0);
+
+		Pricing::set(VotePricing::VoiceCredits);
+		Qv::on_initialize(9);
+		assert_eq!(Qv::current_epoch(), 0);
+		Qv::on_initialize(10);
+		assert_eq!(Qv::current_epoch(), 1);
+		assert_eq!(last_event(), QvEvent::EpochStarted { epoch: 1, credits_per_identity: 1_000 });
+		Qv::on_initialize(20);
+		assert_eq!(Qv::current_epoch(), 2);
+	});
+}
+
+#[test]
+fn new_epoch_refills_voice_credits() {
+	new_test_ext().execute_with(|| {
+		Pricing::set(VotePricing::VoiceCredits);
+		EpochLength::set(10);
+		let who = Origin::signed(10);
+		set_verified_identity(who.clone());
+		assert_ok!(Qv::reserve_an_amount_of_token(who.clone(), 600));
+		assert_eq!(Qv::voice_credits(&10), 400);
+
+		Qv::on_initialize(10);
+		assert_eq!(Qv::voice_credits(&10), 1_000);
+		assert_ok!(Qv::reserve_an_amount_of_token(who, 1_000));
+		assert_eq!(Qv::voice_credits(&10), 0);
+	});
+}
+
+#[test]
+fn capped_fraction_of_unspent_voice_credits_carries_over() {
+	new_test_ext().execute_with(|| {
+		Pricing::set(VotePricing::VoiceCredits);
+		EpochLength::set(10);
+		CarryOver::set(Perbill::from_percent(50));
+		MaxCarryOver::set(150);
+		for (who, amount) in [(10, 600), (20, 900)] {
+			set_verified_identity(Origin::signed(who));
+			assert_ok!(Qv::reserve_an_amount_of_token(Origin::signed(who), amount));
+		}
+
+		Qv::on_initialize(10);
+		// Half of 400 is more than the cap
+		assert_eq!(Qv::voice_credits(&10), 1_000 + 150);
+		assert_eq!(Qv::voice_credits(&20), 1_000 + 50);
+		assert_ok!(Qv::reserve_an_amount_of_token(Origin::signed(20), 1_050));
+
+		Qv::on_initialize(20);
+		Qv::on_initialize(30);
+		// Account 10 skipped an epoch, in which it left its whole budget unspent
+		assert_eq!(Qv::voice_credits(&10), 1_000 + 150);
+		assert_eq!(Qv::voice_credits(&20), 1_000 + 150);
+	});
+}
+
+#[test]
+fn budget_is_refilled_once_however_long_it_was_unused() {
+	new_test_ext().execute_with(|| {
+		Pricing::set(VotePricing::VoiceCredits);
+		CarryOver::set(Perbill::from_percent(50));
+		MaxCarryOver::set(600);
+		set_verified_identity(Origin::signed(10));
+		assert_ok!(Qv::reserve_an_amount_of_token(Origin::signed(10), 600));
+
+		// Half of the 1_200 of the epoch in between, which carried over half of 400
+		CurrentEpoch::<Test>::put(2);
+		assert_eq!(Qv::voice_credits(&10), 1_000 + 600);
+		// Carrying over half of every epoch's budget converges to twice the allotment
+		CurrentEpoch::<Test>::put(u32::MAX);
+		assert_eq!(Qv::voice_credits(&10), 1_000 + 600);
+		CarryOver::set(Perbill::one());
+		assert_eq!(Qv::voice_credits(&10), 1_000 + 600);
+	});
+}
+
+const CLAIM_CODE: [u8; 32] = [7; 32];
+
+fn claim_commitment() -> sp_core::H256 {
+	BlakeTwo256::hash(&CLAIM_CODE)
+}
+
+/// Commit `who` to claiming with `code`, and move on to the next block, in which it can claim.
+fn commit_claim(who: u64, code: [u8; 32]) {
+	assert_ok!(Qv::commit_claim(Origin::signed(who), BlakeTwo256::hash_of(&(code, who))));
+	System::set_block_number(System::block_number() + 1);
+}
+
+#[test]
+fn claimed_identity_makes_the_account_eligible() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_noop!(
+			Qv::create_identity(Origin::signed(1), claim_commitment()),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Qv::create_identity(RawOrigin::Root.into(), claim_commitment()));
+		assert_eq!(last_event(), QvEvent::IdentityGenerated { commitment: claim_commitment() });
+		assert_eq!(Qv::identity_map(claim_commitment()), Some(ClaimState::Unclaimed));
+		assert_noop!(Qv::ensure_verified(&10), Error::<Test>::NoIdentity);
+
+		commit_claim(10, CLAIM_CODE);
+		assert_ok!(Qv::claim_identity(Origin::signed(10), CLAIM_CODE));
+		assert_eq!(
+			last_event(),
+			QvEvent::IdentityClaimed { commitment: claim_commitment(), who: 10 }
+		);
+		assert_eq!(Qv::identity_map(claim_commitment()), Some(ClaimState::Claimed(10)));
+		assert_eq!(Qv::claimed_identity_of(10), Some(claim_commitment()));
+		assert_ok!(Qv::reserve_an_amount_of_token(Origin::signed(10), SMALL_AMOUNT));
+	});
+}
+
+#[test]
+fn try_claim_identity() {
+	new_test_ext().execute_with(|| {
+		commit_claim(10, CLAIM_CODE);
+		assert_noop!(
+			Qv::claim_identity(Origin::signed(10), CLAIM_CODE),
+			Error::<Test>::IdentityNotFound
+		);
+		assert_ok!(Qv::create_identity(RawOrigin::Root.into(), claim_commitment()));
+		assert_noop!(
+			Qv::create_identity(RawOrigin::Root.into(), claim_commitment()),
+			Error::<Test>::IdentityAlreadyExists
+		);
+
+		assert_ok!(Qv::claim_identity(Origin::signed(10), CLAIM_CODE));
+		commit_claim(20, CLAIM_CODE);
+		assert_noop!(
+			Qv::claim_identity(Origin::signed(20), CLAIM_CODE),
+			Error::<Test>::IdentityAlreadyClaimed
+		);
+
+		let other_code = [8; 32];
+		assert_ok!(Qv::create_identity(RawOrigin::Root.into(), BlakeTwo256::hash(&other_code)));
+		assert_noop!(
+			Qv::claim_identity(Origin::signed(10), other_code),
+			Error::<Test>::AccountAlreadyClaimed
+		);
+	});
+}
+
+#[test]
+fn deleted_identity_no_longer_makes_the_account_eligible() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Qv::create_identity(RawOrigin::Root.into(), claim_commitment()));
+		commit_claim(10, CLAIM_CODE);
+		assert_ok!(Qv::claim_identity(Origin::signed(10), CLAIM_CODE));
+
+		assert_ok!(Qv::delete_identity(RawOrigin::Root.into(), claim_commitment()));
+		assert_eq!(last_event(), QvEvent::IdentityDeleted { commitment: claim_commitment() });
+		assert_eq!(Qv::identity_map(claim_commitment()), None);
+		assert_eq!(Qv::claimed_identity_of(10), None);
+		assert_noop!(
+			Qv::reserve_an_amount_of_token(Origin::signed(10), SMALL_AMOUNT),
+			Error::<Test>::NoIdentity
+		);
+		assert_noop!(
+			Qv::delete_identity(RawOrigin::Root.into(), claim_commitment()),
+			Error::<Test>::IdentityNotFound
+		);
+		commit_claim(10, CLAIM_CODE);
+		assert_noop!(
+			Qv::claim_identity(Origin::signed(10), CLAIM_CODE),
+			Error::<Test>::IdentityNotFound
+		);
+	});
+}
+
+#[test]
+fn copied_claim_code_can_not_be_claimed_by_another_account() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Qv::create_identity(RawOrigin::Root.into(), claim_commitment()));
+		assert_noop!(
+			Qv::claim_identity(Origin::signed(10), CLAIM_CODE),
+			Error::<Test>::NoClaimCommitment
+		);
+		let commitment = BlakeTwo256::hash_of(&(CLAIM_CODE, 10u64));
+		assert_ok!(Qv::commit_claim(Origin::signed(10), commitment));
+		assert_eq!(last_event(), QvEvent::ClaimCommitted { who: 10 });
+		assert_noop!(
+			Qv::claim_identity(Origin::signed(10), CLAIM_CODE),
+			Error::<Test>::ClaimRevealTooEarly
+		);
+
+		// Account 20 copies the claim code that account 10 reveals, and the commitment it made
+		System::set_block_number(2);
+		assert_ok!(Qv::commit_claim(Origin::signed(20), commitment));
+		System::set_block_number(3);
+		assert_noop!(
+			Qv::claim_identity(Origin::signed(20), CLAIM_CODE),
+			Error::<Test>::ClaimCommitmentMismatch
+		);
+		assert_ok!(Qv::claim_identity(Origin::signed(10), CLAIM_CODE));
+		assert_eq!(Qv::claim_commitment_of(10), None);
+
+		// Committing for itself only works after the code is revealed, when it is too late
+		commit_claim(20, CLAIM_CODE);
+		assert_noop!(
+			Qv::claim_identity(Origin::signed(20), CLAIM_CODE),
+			Error::<Test>::IdentityAlreadyClaimed
+		);
+	});
+}
+
+#[test]
+fn launch_votes_get_refunded_after_identity_is_cleared() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+		assert_ok!(Identity::clear_identity(Origin::signed(20)));
+
+		assert_ok!(Referenda::cancel(RawOrigin::Root.into(), 0));
+		assert_eq!(Balances::reserved_balance(20), 0);
+		assert_eq!(Balances::free_balance(20), 110);
+	});
+}
+
+#[test]
+fn launch_votes_of_cleared_identity_get_voided() {
+	new_test_ext().execute_with(|| {
+		back_referendum_with_three_backers();
+		assert_noop!(Qv::void_votes(Origin::signed(10), 20, 0), Error::<Test>::StillVerified);
+
+		assert_ok!(Identity::clear_identity(Origin::signed(20)));
+		assert_ok!(Qv::void_votes(Origin::signed(10), 20, 0));
+		assert_eq!(last_event(), QvEvent::VotesVoided { who: 20, index: 0 });
+		assert_eq!(Qv::launch_totals(0), LaunchTally { backers: 3, votes: 3 + 7 });
+		assert_eq!(Qv::depositors(0, 20), None);
+		assert_eq!(Balances::reserved_balance(20), 0);
+		assert_eq!(Balances::free_balance(20), 110);
+
+		assert_noop!(Qv::void_votes(Origin::signed(10), 20, 0), Error::<Test>::NothingToVoid);
+	});
+}
+
+#[test]
+fn deciding_vote_of_cleared_identity_gets_voided() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+
+		let voter = Origin::signed(41);
+		set_verified_identity(voter.clone());
+		assert_ok!(Qv::vote_nay(voter.clone(), 10, 0));
+		assert_ok!(Identity::clear_identity(voter));
+		assert_eq!(Balances::free_balance(41), 9_910);
+
+		assert_ok!(Qv::void_votes(Origin::signed(40), 41, 0));
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!(tally, QuadraticTally { ayes: 0, nays: 0, supporters: 0, electorate: 1 });
+		assert_eq!(Balances::free_balance(41), 10_010);
+		assert_noop!(Qv::remove_vote(Origin::signed(41), 0), Error::<Test>::StillOngoing);
+
+		run_to(DECISION_STARTS + ONE_MONTH);
+		assert_noop!(Qv::void_votes(Origin::signed(40), 41, 0), ReferendaError::<Test>::NotOngoing);
+		assert_noop!(Qv::remove_vote(Origin::signed(41), 0), Error::<Test>::NotVoted);
+	});
+}
+
+#[test]
+fn quadratic_tally_measures_support_in_voters() {
+	type Tally = QuadraticTally;
+	let mut tally = <Tally as VoteTally<u32, u8>>::new(0);
+	assert_eq!(<Tally as VoteTally<u32, u8>>::support(&tally, 0), Perbill::zero());
+	assert_eq!(<Tally as VoteTally<u32, u8>>::approval(&tally, 0), Perbill::zero());
+
+	tally.snapshot_electorate(4);
+	tally.add_aye(9);
+	tally.add_nay(3);
+	assert_eq!(<Tally as VoteTally<u32, u8>>::ayes(&tally, 0), 9);
+	assert_eq!(<Tally as VoteTally<u32, u8>>::support(&tally, 0), Perbill::from_percent(25));
+	assert_eq!(<Tally as VoteTally<u32, u8>>::approval(&tally, 0), Perbill::from_percent(75));
+
+	tally.add_aye(1);
+	tally.remove_nay(3);
+	assert_eq!(<Tally as VoteTally<u32, u8>>::support(&tally, 0), Perbill::from_percent(50));
+	assert_eq!(<Tally as VoteTally<u32, u8>>::approval(&tally, 0), Perbill::one());
+	tally.remove_aye(9);
+	assert_eq!(tally, QuadraticTally { ayes: 1, nays: 0, supporters: 1, electorate: 4 });
+}
+
+#[test]
+fn electorate_is_snapshotted_when_deciding_begins() {
+	new_test_ext().execute_with(|| {
+		Electorate::set(4);
+		launch_referendum();
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!(tally.electorate, 0);
+
+		run_to(DECISION_STARTS);
+		Electorate::set(100);
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		assert_ok!(Qv::vote_aye(voter, 2, 0));
+
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!(tally, QuadraticTally { ayes: 2, nays: 0, supporters: 1, electorate: 4 });
+		assert_eq!(
+			<QuadraticTally as VoteTally<u32, u8>>::support(&tally, 0),
+			Perbill::from_percent(25)
+		);
+	});
+}
+
+#[test]
+fn claimed_identities_count_as_the_electorate() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Qv::create_identity(RawOrigin::Root.into(), claim_commitment()));
+		assert_eq!(ClaimedIdentities::<Test>::get(), 0);
+		commit_claim(10, CLAIM_CODE);
+		assert_ok!(Qv::claim_identity(Origin::signed(10), CLAIM_CODE));
+		assert_eq!(ClaimedIdentities::<Test>::get(), 1);
+
+		assert_ok!(Qv::delete_identity(RawOrigin::Root.into(), claim_commitment()));
+		assert_eq!(ClaimedIdentities::<Test>::get(), 0);
+	});
+}
+
+const SALT: [u8; 32] = [3; 32];
+
+/// The commitment of `who` to a secret ballot of `votes` on referendum `index`.
+fn ballot_commitment(who: u64, index: u32, aye: bool, votes: u32) -> H256 {
+	BlakeTwo256::hash_of(&(who, index, aye, votes, SALT))
+}
+
+#[test]
+fn secret_ballots_must_be_revealed_within_the_decision_period() {
+	new_test_ext().execute_with(|| {
+		CommitReveal::set(true);
+		CommitPeriod::set(ONE_MONTH - 10);
+		RevealPeriod::set(11);
+		set_verified_identity(Origin::signed(30));
+		assert_noop!(initiate_proposal_one(30), Error::<Test>::BallotPeriodsTooLong);
+
+		RevealPeriod::set(10);
+		assert_ok!(initiate_proposal_one(30));
+	});
+}
+
+#[test]
+fn secret_ballot_is_counted_when_revealed() {
+	new_test_ext().execute_with(|| {
+		CommitReveal::set(true);
+		launch_referendum();
+		run_to(DECISION_STARTS);
+
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		assert_noop!(Qv::vote_aye(voter.clone(), 10, 0), Error::<Test>::CommitRevealOnly);
+		let commitment = ballot_commitment(40, 0, true, 10);
+		assert_ok!(Qv::commit_vote(voter.clone(), 0, commitment, 20));
+		assert_eq!(last_event(), QvEvent::VoteCommitted { index: 0 });
+		assert_eq!(Balances::free_balance(40), 9_600);
+		assert_noop!(
+			Qv::commit_vote(voter.clone(), 0, commitment, 20),
+			Error::<Test>::AlreadyVoted
+		);
+		assert_noop!(
+			Qv::reveal_vote(voter.clone(), 0, true, 10, SALT),
+			Error::<Test>::NotRevealPhase
+		);
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!(tally, QuadraticTally { ayes: 0, nays: 0, supporters: 0, electorate: 1 });
+
+		run_to(DECISION_STARTS + 10);
+		assert_noop!(
+			Qv::commit_vote(Origin::signed(41), 0, commitment, 20),
+			Error::<Test>::NotCommitPhase
+		);
+		assert_noop!(
+			Qv::reveal_vote(voter.clone(), 0, true, 10, [0; 32]),
+			Error::<Test>::CommitmentMismatch
+		);
+		assert_noop!(
+			Qv::reveal_vote(voter.clone(), 0, false, 10, SALT),
+			Error::<Test>::CommitmentMismatch
+		);
+		assert_ok!(Qv::reveal_vote(voter.clone(), 0, true, 10, SALT));
+		assert_eq!(last_event(), QvEvent::VotesCast { aye: true, number_of_votes: 10, index: 0 });
+		assert_eq!(Balances::free_balance(40), 9_900);
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!(tally, QuadraticTally { ayes: 10, nays: 0, supporters: 1, electorate: 1 });
+		assert_noop!(Qv::reveal_vote(voter, 0, true, 10, SALT), Error::<Test>::NoCommitment);
+	});
+}
+
+#[test]
+fn copied_commitment_can_not_be_revealed_by_another_account() {
+	new_test_ext().execute_with(|| {
+		CommitReveal::set(true);
+		launch_referendum();
+		run_to(DECISION_STARTS);
+
+		for account in [40, 41] {
+			set_verified_identity(Origin::signed(account));
+			assert_ok!(Qv::commit_vote(
+				Origin::signed(account),
+				0,
+				ballot_commitment(40, 0, true, 10),
+				10
+			));
+		}
+
+		run_to(DECISION_STARTS + 10);
+		assert_noop!(
+			Qv::reveal_vote(Origin::signed(41), 0, true, 10, SALT),
+			Error::<Test>::CommitmentMismatch
+		);
+		assert_ok!(Qv::reveal_vote(Origin::signed(40), 0, true, 10, SALT));
+	});
+}
+
+#[test]
+fn try_reveal_more_votes_than_committed() {
+	new_test_ext().execute_with(|| {
+		CommitReveal::set(true);
+		launch_referendum();
+		run_to(DECISION_STARTS);
+
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		let commitment = ballot_commitment(40, 0, false, 30);
+		assert_ok!(Qv::commit_vote(voter.clone(), 0, commitment, 20));
+
+		run_to(DECISION_STARTS + 10);
+		assert_noop!(
+			Qv::reveal_vote(voter.clone(), 0, false, 30, SALT),
+			Error::<Test>::RevealExceedsMaximum
+		);
+
+		run_to(DECISION_STARTS + 20);
+		assert_noop!(Qv::reveal_vote(voter, 0, false, 30, SALT), Error::<Test>::NotRevealPhase);
+	});
+}
+
+#[test]
+fn unrevealed_ballots_are_refunded_or_forfeited() {
+	new_test_ext().execute_with(|| {
+		CommitReveal::set(true);
+		launch_referendum();
+		run_to(DECISION_STARTS);
+
+		for account in [40, 41] {
+			let voter = Origin::signed(account);
+			set_verified_identity(voter.clone());
+			let commitment = ballot_commitment(account, 0, true, 10);
+			assert_ok!(Qv::commit_vote(voter, 0, commitment, 10));
+			assert_eq!(Balances::free_balance(account), 9_900);
+		}
+
+		run_to(DECISION_STARTS + 10);
+		assert_noop!(
+			Qv::settle_unrevealed(Origin::signed(30), 40, 0),
+			Error::<Test>::StillRevealing
+		);
+
+		run_to(DECISION_STARTS + 20);
+		assert_ok!(Qv::settle_unrevealed(Origin::signed(30), 40, 0));
+		assert_eq!(
+			last_event(),
+			QvEvent::UnrevealedSettled { who: 40, index: 0, forfeited: false }
+		);
+		assert_eq!(Balances::free_balance(40), 10_000);
+
+		ForfeitUnrevealed::set(true);
+		let total_before = Balances::total_balance(&41);
+		assert_ok!(Qv::settle_unrevealed(Origin::signed(30), 41, 0));
+		assert_eq!(last_event(), QvEvent::UnrevealedSettled { who: 41, index: 0, forfeited: true });
+		assert_eq!(Balances::free_balance(41), 9_900);
+		assert_eq!(Balances::total_balance(&41), total_before - 100);
+		assert_noop!(Qv::settle_unrevealed(Origin::signed(30), 41, 0), Error::<Test>::NoCommitment);
+	});
+}
+
+#[test]
+fn try_commit_vote_when_commit_reveal_is_disabled() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		let commitment = ballot_commitment(40, 0, true, 10);
+		assert_noop!(
+			Qv::commit_vote(voter, 0, commitment, 10),
+			Error::<Test>::CommitRevealDisabled
+		);
+	});
+}
+
+#[test]
+fn registrar_verified_voters_count_as_the_electorate() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		set_judged_identity(Origin::signed(10), 0, Judgement::Reasonable);
+		set_judged_identity(Origin::signed(20), 0, Judgement::KnownGood);
+		assert_eq!(EligibleVoters::<Test>::get(), 0);
+
+		// A verified account is counted before it can vote, not when it first votes
+		assert_noop!(
+			Qv::reserve_an_amount_of_token(Origin::signed(10), SMALL_AMOUNT),
+			Error::<Test>::NotVerifiedVoter
+		);
+		assert_noop!(Qv::register_voter(Origin::signed(1), 30), Error::<Test>::NoIdentity);
+		assert_ok!(Qv::register_voter(Origin::signed(1), 10));
+		assert_eq!(last_event(), QvEvent::VoterRegistered { who: 10 });
+		assert_ok!(Qv::register_voter(Origin::signed(1), 20));
+		assert_noop!(Qv::register_voter(Origin::signed(1), 20), Error::<Test>::AlreadyVoter);
+		assert_eq!(EligibleVoters::<Test>::get(), 2);
+		assert_eq!(ClaimedIdentities::<Test>::get(), 0);
+
+		assert_ok!(Qv::reserve_an_amount_of_token(Origin::signed(10), SMALL_AMOUNT));
+		assert_ok!(Qv::reserve_an_amount_of_token(Origin::signed(20), SMALL_AMOUNT));
+		assert_eq!(EligibleVoters::<Test>::get(), 2);
+
+		// Claiming an identity does not count the account twice
+		assert_ok!(Qv::create_identity(RawOrigin::Root.into(), claim_commitment()));
+		commit_claim(10, CLAIM_CODE);
+		assert_ok!(Qv::claim_identity(Origin::signed(10), CLAIM_CODE));
+		assert_eq!(EligibleVoters::<Test>::get(), 2);
+		assert_noop!(Qv::register_voter(Origin::signed(1), 10), Error::<Test>::AlreadyVoter);
+
+		assert_noop!(Qv::forget_voter(Origin::signed(1), 20), Error::<Test>::StillVerified);
+		assert_ok!(Identity::clear_identity(Origin::signed(20)));
+		assert_ok!(Qv::forget_voter(Origin::signed(1), 20));
+		assert_eq!(last_event(), QvEvent::VoterForgotten { who: 20 });
+		assert_eq!(EligibleVoters::<Test>::get(), 1);
+		assert_noop!(Qv::forget_voter(Origin::signed(1), 20), Error::<Test>::NotVerifiedVoter);
+	});
+}
+
+/// The key behind the anonymous voter commitment of `account`, a fresh account of its own.
+fn key_of(account: u64) -> u64 {
+	account + 1_000
+}
+
+fn anonymous_commitment(account: u64) -> H256 {
+	BlakeTwo256::hash_of(&key_of(account))
+}
+
+fn nullifier(account: u64, index: u32) -> H256 {
+	BlakeTwo256::hash_of(&(key_of(account), index))
+}
+
+/// Register the anonymous voter commitments of verified `accounts`, in order.
+fn register_anonymous_voters(accounts: &[u64]) {
+	for &account in accounts {
+		set_verified_identity(Origin::signed(account));
+		assert_ok!(Qv::register_anonymous_voter(
+			Origin::signed(account),
+			anonymous_commitment(account)
+		));
+	}
+}
+
+/// The siblings of the nodes on the way from `leaves[position]` up to the root of the voter set
+/// with `leaves`.
+fn merkle_path(leaves: &[H256], position: usize) -> Vec<H256> {
+	let mut level = leaves.to_vec();
+	let mut index = position;
+	let mut path = Vec::new();
+	for height in 0..<Test as crate::Config>::MerkleDepth::get() {
+		if level.len() % 2 == 1 {
+			level.push(empty_subtree::<BlakeTwo256>(height));
+		}
+		path.push(level[index ^ 1]);
+		level = level
+			.chunks(2)
+			.map(|pair| merkle_node::<BlakeTwo256>(&pair[0], &pair[1]))
+			.collect();
+		index /= 2;
+	}
+	path
+}
+
+/// The proof that the commitment of `accounts[position]` is in the voter set of the commitments
+/// of `accounts`, bound to `signal`.
+fn merkle_proof(accounts: &[u64], position: usize, signal: H256) -> AnonymousProof {
+	let leaves: Vec<H256> = accounts.iter().map(|&account| anonymous_commitment(account)).collect();
+	let key = key_of(accounts[position]);
+	MerkleProof {
+		key,
+		signature: TestSignature(key, signal.encode()),
+		leaf_index: position as u32,
+		path: merkle_path(&leaves, position).try_into().unwrap(),
+	}
+}
+
+type AnonymousProof = MerkleProofOf<BlakeTwo256, TestSignature>;
+
+/// Cast an anonymous ballot on referendum `index`, paid for by `payer`, with the proof that the
+/// commitment of `accounts[position]` is in the voter set of the commitments of `accounts`.
+fn vote_anonymously(
+	payer: u64,
+	index: u32,
+	aye: bool,
+	votes: u32,
+	accounts: &[u64],
+	position: usize,
+) -> sp_runtime::DispatchResult {
+	let signal = Qv::ballot_signal(index, aye, votes, &payer);
+	Qv::vote_anonymously(
+		Origin::signed(payer),
+		index,
+		aye,
+		votes,
+		nullifier(accounts[position], index),
+		merkle_proof(accounts, position, signal),
+	)
+}
+
+#[test]
+fn voter_set_root_matches_the_merkle_tree() {
+	new_test_ext().execute_with(|| {
+		let accounts = [40, 41, 10];
+		register_anonymous_voters(&accounts);
+		assert_eq!(Qv::anonymous_voter_count(), 3);
+		assert_eq!(Qv::anonymous_voter_of(41), Some(anonymous_commitment(41)));
+
+		let root = Qv::anonymous_voter_root();
+		let signal = Qv::ballot_signal(0, true, 1, &20);
+		for (position, &account) in accounts.iter().enumerate() {
+			assert!(MerkleMembership::<BlakeTwo256, TestSignature>::verify(
+				&root,
+				0,
+				&nullifier(account, 0),
+				&signal,
+				&merkle_proof(&accounts, position, signal)
+			));
+		}
+		assert!(!MerkleMembership::<BlakeTwo256, TestSignature>::verify(
+			&root,
+			1,
+			&nullifier(40, 0),
+			&signal,
+			&merkle_proof(&accounts, 0, signal)
+		));
+		// No verifier, no anonymous ballots
+		assert!(!<() as VerifyMembership<H256>>::verify(&root, 0, &nullifier(40, 0), &signal, &()));
+	});
+}
+
+#[test]
+fn anonymous_ballot_is_counted_without_naming_the_voter() {
+	new_test_ext().execute_with(|| {
+		let accounts = [40, 41];
+		launch_referendum();
+		register_anonymous_voters(&accounts);
+		assert_noop!(vote_anonymously(20, 0, true, 10, &accounts, 1), Error::<Test>::NoVoterSet);
+
+		run_to(DECISION_STARTS);
+		assert_ok!(vote_anonymously(20, 0, true, 10, &accounts, 1));
+		assert_eq!(
+			last_event(),
+			QvEvent::AnonymousVotesCast {
+				aye: true,
+				number_of_votes: 10,
+				index: 0,
+				nullifier: nullifier(41, 0)
+			}
+		);
+		assert_eq!(Balances::free_balance(20), 10);
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!(tally, QuadraticTally { ayes: 10, nays: 0, supporters: 1, electorate: 1 });
+		assert_noop!(vote_anonymously(10, 0, false, 1, &accounts, 1), Error::<Test>::NullifierUsed);
+		assert_noop!(vote_anonymously(20, 0, false, 1, &accounts, 0), Error::<Test>::AlreadyVoted);
+		assert_noop!(Qv::remove_vote(Origin::signed(20), 0), Error::<Test>::StillOngoing);
+
+		run_to(DECISION_STARTS + 2);
+		assert!(!Referenda::is_ongoing(0));
+		assert_ok!(Qv::remove_vote(Origin::signed(20), 0));
+		assert_eq!(Balances::free_balance(20), 110);
+	});
+}
+
+#[test]
+fn try_invalid_anonymous_ballots() {
+	new_test_ext().execute_with(|| {
+		let accounts = [40, 41];
+		launch_referendum();
+		register_anonymous_voters(&accounts);
+		assert_noop!(
+			Qv::register_anonymous_voter(Origin::signed(40), anonymous_commitment(40)),
+			Error::<Test>::AlreadyRegistered
+		);
+		assert_noop!(
+			Qv::register_anonymous_voter(Origin::signed(20), anonymous_commitment(20)),
+			Error::<Test>::NoIdentity
+		);
+
+		run_to(DECISION_STARTS);
+		// Registered after deciding began, so not in the referendum's voter set
+		register_anonymous_voters(&[10]);
+		assert_noop!(
+			vote_anonymously(20, 0, true, 1, &[40, 41, 10], 2),
+			Error::<Test>::InvalidMembershipProof
+		);
+		// The nullifier of another referendum
+		let signal = Qv::ballot_signal(0, true, 1, &20);
+		assert_noop!(
+			Qv::vote_anonymously(
+				Origin::signed(20),
+				0,
+				true,
+				1,
+				nullifier(40, 1),
+				merkle_proof(&accounts, 0, signal)
+			),
+			Error::<Test>::InvalidMembershipProof
+		);
+		// A key that is not behind any commitment
+		let mut proof = merkle_proof(&accounts, 0, signal);
+		proof.key = key_of(42);
+		proof.signature = TestSignature(key_of(42), signal.encode());
+		assert_noop!(
+			Qv::vote_anonymously(Origin::signed(20), 0, true, 1, nullifier(42, 0), proof),
+			Error::<Test>::InvalidMembershipProof
+		);
+
+		Pricing::set(VotePricing::VoiceCredits);
+		assert_noop!(
+			vote_anonymously(20, 0, true, 1, &accounts, 0),
+			Error::<Test>::NotCurrencyPriced
+		);
+	});
+}
+
+#[test]
+fn anonymous_proof_can_not_be_taken_for_another_ballot() {
+	new_test_ext().execute_with(|| {
+		let accounts = [40, 41];
+		launch_referendum();
+		register_anonymous_voters(&accounts);
+		run_to(DECISION_STARTS);
+
+		// Somebody who sees the pending ballot can not cast it from their own account, or turn
+		// it around
+		let proof = merkle_proof(&accounts, 0, Qv::ballot_signal(0, true, 10, &20));
+		for (payer, aye, votes) in [(10, true, 10), (20, false, 10), (20, true, 1)] {
+			assert_noop!(
+				Qv::vote_anonymously(
+					Origin::signed(payer),
+					0,
+					aye,
+					votes,
+					nullifier(40, 0),
+					proof.clone()
+				),
+				Error::<Test>::InvalidMembershipProof
+			);
+		}
+		assert_ok!(Qv::vote_anonymously(Origin::signed(20), 0, true, 10, nullifier(40, 0), proof));
+	});
+}
+
+#[test]
+fn registered_anonymous_voter_can_not_vote_directly_as_well() {
+	new_test_ext().execute_with(|| {
+		let accounts = [40, 41];
+		launch_referendum();
+		register_anonymous_voters(&accounts);
+		let direct_voter = Origin::signed(10);
+		set_verified_identity(direct_voter.clone());
+		// Before deciding began, a direct voter could still register a commitment in the set
+		assert_noop!(Qv::vote_aye(direct_voter.clone(), 5, 0), Error::<Test>::NoVoterSet);
+
+		run_to(DECISION_STARTS);
+		assert_noop!(Qv::vote_aye(Origin::signed(40), 10, 0), Error::<Test>::RegisteredAnonymously);
+		assert_ok!(vote_anonymously(20, 0, true, 10, &accounts, 0));
+
+		assert_ok!(Qv::vote_aye(direct_voter, 5, 0));
+		register_anonymous_voters(&[10]);
+		assert_noop!(
+			vote_anonymously(1, 0, true, 1, &[40, 41, 10], 2),
+			Error::<Test>::InvalidMembershipProof
+		);
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!(tally, QuadraticTally { ayes: 15, nays: 0, supporters: 2, electorate: 1 });
+	});
+}
+
+#[test]
+fn revoked_anonymous_voter_is_left_out_of_later_voter_sets() {
+	new_test_ext().execute_with(|| {
+		let accounts = [40, 41, 10];
+		register_anonymous_voters(&accounts);
+		let leaves: Vec<H256> =
+			accounts.iter().map(|&account| anonymous_commitment(account)).collect();
+		let path = |leaves: &[H256], position| merkle_path(leaves, position).try_into().unwrap();
+		assert_noop!(
+			Qv::revoke_anonymous_voter(Origin::signed(1), 41, 1, path(&leaves, 1)),
+			Error::<Test>::StillVerified
+		);
+
+		assert_ok!(Identity::clear_identity(Origin::signed(41)));
+		assert_noop!(
+			Qv::revoke_anonymous_voter(Origin::signed(1), 41, 0, path(&leaves, 0)),
+			Error::<Test>::InvalidMembershipProof
+		);
+		assert_ok!(Qv::revoke_anonymous_voter(Origin::signed(1), 41, 1, path(&leaves, 1)));
+		let root = Qv::anonymous_voter_root();
+		assert_eq!(
+			last_event(),
+			QvEvent::AnonymousVoterRevoked { commitment: anonymous_commitment(41), root }
+		);
+		assert_eq!(Qv::anonymous_voter_of(41), None);
+		assert_noop!(
+			Qv::revoke_anonymous_voter(Origin::signed(1), 41, 1, path(&leaves, 1)),
+			Error::<Test>::NotRegistered
+		);
+
+		// Later commitments are added next to the emptied leaf
+		register_anonymous_voters(&[20]);
+		let leaves = [leaves[0], H256::default(), leaves[2], anonymous_commitment(20)];
+		let proof = |account, position, payer| {
+			let key = key_of(account);
+			let signal = Qv::ballot_signal(0, true, 1, &payer);
+			MerkleProof {
+				key,
+				signature: TestSignature(key, signal.encode()),
+				leaf_index: position,
+				path: path(&leaves, position as usize),
+			}
+		};
+		launch_referendum();
+		run_to(DECISION_STARTS);
+		assert_noop!(
+			vote_anonymously(1, 0, true, 1, &accounts, 1),
+			Error::<Test>::InvalidMembershipProof
+		);
+		assert_ok!(Qv::vote_anonymously(
+			Origin::signed(1),
+			0,
+			true,
+			1,
+			nullifier(20, 0),
+			proof(20, 3, 1)
+		));
+		assert_ok!(Qv::vote_anonymously(
+			Origin::signed(2),
+			0,
+			true,
+			1,
+			nullifier(40, 0),
+			proof(40, 0, 2)
+		));
+	});
+}
+
+#[test]
+fn anonymous_voter_can_replace_its_commitment() {
+	new_test_ext().execute_with(|| {
+		let accounts = [40, 41];
+		register_anonymous_voters(&accounts);
+		let leaves: Vec<H256> =
+			accounts.iter().map(|&account| anonymous_commitment(account)).collect();
+		let path = merkle_path(&leaves, 0).try_into().unwrap();
+		// The voter's new key, as if it were account 42's
+		let replacement = anonymous_commitment(42);
+		assert_noop!(
+			Qv::replace_anonymous_voter(Origin::signed(10), replacement, 0, path.clone()),
+			Error::<Test>::NoIdentity
+		);
+
+		assert_ok!(Qv::replace_anonymous_voter(Origin::signed(40), replacement, 0, path));
+		let root = Qv::anonymous_voter_root();
+		assert_eq!(
+			last_event(),
+			QvEvent::AnonymousVoterRegistered { commitment: replacement, root }
+		);
+		assert_eq!(Qv::anonymous_voter_of(40), Some(replacement));
+		assert_eq!(Qv::anonymous_voter_count(), 3);
+
+		// Only the new key is in the voter set of later referenda
+		launch_referendum();
+		run_to(DECISION_STARTS);
+		assert_noop!(
+			vote_anonymously(1, 0, true, 1, &accounts, 0),
+			Error::<Test>::InvalidMembershipProof
+		);
+		let leaves = [H256::default(), leaves[1], replacement];
+		let signal = Qv::ballot_signal(0, true, 1, &1);
+		let proof = MerkleProof {
+			key: key_of(42),
+			signature: TestSignature(key_of(42), signal.encode()),
+			leaf_index: 2,
+			path: merkle_path(&leaves, 2).try_into().unwrap(),
+		};
+		assert_ok!(Qv::vote_anonymously(Origin::signed(1), 0, true, 1, nullifier(42, 0), proof));
+	});
+}
+
+#[test]
+fn try_register_in_full_voter_set() {
+	new_test_ext().execute_with(|| {
+		AnonymousVoterCount::<Test>::put(16);
+		set_verified_identity(Origin::signed(40));
+		assert_noop!(
+			Qv::register_anonymous_voter(Origin::signed(40), anonymous_commitment(40)),
+			Error::<Test>::VoterSetFull
+		);
+	});
+}
+
+const COORDINATOR: u64 = 1;
+const COORDINATOR_SECRET: [u8; 32] = [9; 32];
+
+/// Initiate a referendum whose votes are sent as messages to `COORDINATOR`, and launch it.
+fn launch_maci_poll() {
+	System::set_block_number(1);
+	set_verified_identity(Origin::signed(30));
+	assert_ok!(initiate_proposal_one(30));
+	assert_ok!(Qv::open_maci_poll(RawOrigin::Root.into(), 0, COORDINATOR, [5; 32]));
+	assert_eq!(last_event(), QvEvent::MaciPollOpened { index: 0, coordinator: COORDINATOR });
+
+	for backer in [31, 32] {
+		let launch_voter = Origin::signed(backer);
+		set_verified_identity(launch_voter.clone());
+		assert_ok!(Qv::cast_launch_votes(launch_voter, 500, 0));
+	}
+}
+
+fn message() -> BoundedVec<u8, ConstU32<256>> {
+	vec![0xab; 109].try_into().unwrap()
+}
+
+#[test]
+fn maci_poll_counts_the_coordinators_tally() {
+	new_test_ext().execute_with(|| {
+		launch_maci_poll();
+		let voter = Origin::signed(40);
+		set_verified_identity(voter.clone());
+		assert_ok!(Qv::maci_sign_up(voter.clone(), 0, [40; 32]));
+		assert_eq!(
+			last_event(),
+			QvEvent::MaciSignedUp { index: 0, state_index: 0, public_key: [40; 32] }
+		);
+		assert_noop!(Qv::maci_sign_up(voter.clone(), 0, [41; 32]), Error::<Test>::AlreadySignedUp);
+		assert_noop!(Qv::vote_aye(voter, 10, 0), Error::<Test>::MaciPollOnly);
+
+		// Anyone can publish a message, as it is signed inside
+		assert_ok!(Qv::publish_message(Origin::signed(20), 0, [6; 32], message()));
+		assert_eq!(last_event(), QvEvent::MaciMessagePublished { index: 0, position: 0 });
+		let poll = Qv::maci_poll(0).unwrap();
+		assert_eq!((poll.signups, poll.messages), (1, 1));
+		assert_eq!(
+			poll.message_root,
+			BlakeTwo256::hash_of(&(H256::default(), [6u8; 32], message()))
+		);
+
+		run_to(DECISION_STARTS);
+		assert_ok!(Qv::publish_message(Origin::signed(20), 0, [7; 32], message()));
+		let commitment = BlakeTwo256::hash_of(&2);
+		assert_noop!(
+			Qv::publish_maci_tally(Origin::signed(COORDINATOR), 0, 10, 0, 1, commitment),
+			Error::<Test>::NotRevealPhase
+		);
+
+		run_to(DECISION_STARTS + 10);
+		assert_noop!(
+			Qv::publish_message(Origin::signed(20), 0, [8; 32], message()),
+			Error::<Test>::MessagePeriodOver
+		);
+		assert_noop!(Qv::maci_sign_up(Origin::signed(41), 0, [41; 32]), Error::<Test>::NoIdentity);
+		assert_noop!(
+			Qv::publish_maci_tally(Origin::signed(20), 0, 10, 0, 1, commitment),
+			Error::<Test>::NotCoordinator
+		);
+		// The only voter can not have voted both ways, nor more than 100 voice credits afford
+		for (ayes, nays, supporters) in [(10, 2, 1), (11, 0, 1), (0, 0, 2)] {
+			assert_noop!(
+				Qv::publish_maci_tally(
+					Origin::signed(COORDINATOR),
+					0,
+					ayes,
+					nays,
+					supporters,
+					commitment
+				),
+				Error::<Test>::ImplausibleTally
+			);
+		}
+		assert_ok!(Qv::publish_maci_tally(Origin::signed(COORDINATOR), 0, 10, 0, 1, commitment));
+		let message_root = Qv::maci_poll(0).unwrap().message_root;
+		let tally_commitment =
+			maci_verifier::tally_commitment(0, message_root, 100, 10, 0, 1, commitment);
+		let tally = MaciTally { ayes: 10, nays: 0, supporters: 1, commitment, tally_commitment };
+		assert_eq!(last_event(), QvEvent::MaciTallyPublished { index: 0, tally: tally.clone() });
+		assert_eq!(Qv::maci_poll(0).unwrap().tally, Some(tally));
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!(tally, QuadraticTally { ayes: 10, nays: 0, supporters: 1, electorate: 1 });
+		assert_noop!(
+			Qv::publish_maci_tally(Origin::signed(COORDINATOR), 0, 10, 0, 1, commitment),
+			Error::<Test>::TallyAlreadyPublished
+		);
+	});
+}
+
+#[test]
+fn maci_tally_of_the_verifier_is_bound_to_the_message_root() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		set_verified_identity(Origin::signed(30));
+		assert_ok!(initiate_proposal_one(30));
+		let coordinator_key = public_key(COORDINATOR_SECRET);
+		assert_ok!(Qv::open_maci_poll(RawOrigin::Root.into(), 0, COORDINATOR, coordinator_key));
+
+		let voter_key = ed25519::Pair::from_seed(&[40; 32]);
+		set_verified_identity(Origin::signed(40));
+		assert_ok!(Qv::maci_sign_up(Origin::signed(40), 0, voter_key.public().0));
+		let command = Command::signed(0, 0, 1, voter_key.public().0, true, 7, &voter_key);
+		let (ephemeral_key, data) = encrypt(&command, coordinator_key, [6; 32]);
+		assert_ok!(Qv::publish_message(
+			Origin::signed(20),
+			0,
+			ephemeral_key,
+			data.clone().try_into().unwrap()
+		));
+		for backer in [31, 32] {
+			let launch_voter = Origin::signed(backer);
+			set_verified_identity(launch_voter.clone());
+			assert_ok!(Qv::cast_launch_votes(launch_voter, 500, 0));
+		}
+
+		run_to(DECISION_STARTS + 10);
+		let log = MessageLog {
+			index: 0,
+			voice_credits: 100,
+			signups: vec![voter_key.public().0],
+			messages: vec![(ephemeral_key, data)],
+		};
+		let tally = process(&log, COORDINATOR_SECRET);
+		assert_eq!((tally.ayes, tally.nays, tally.supporters), (7, 0, 1));
+		assert_eq!(Qv::maci_poll(0).unwrap().message_root, tally.message_root);
+		assert_ok!(Qv::publish_maci_tally(
+			Origin::signed(COORDINATOR),
+			0,
+			tally.ayes,
+			tally.nays,
+			tally.supporters,
+			tally.commitment
+		));
+		let published = Qv::maci_poll(0).unwrap().tally.unwrap();
+		assert_eq!(published.tally_commitment, tally.tally_commitment);
+	});
+}
+
+#[test]
+fn try_open_maci_poll_after_launch_phase() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		assert_noop!(
+			Qv::open_maci_poll(Origin::signed(30), 0, COORDINATOR, [5; 32]),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Qv::open_maci_poll(RawOrigin::Root.into(), 0, COORDINATOR, [5; 32]),
+			Error::<Test>::LaunchPhaseOver
+		);
+		assert_noop!(
+			Qv::publish_message(Origin::signed(20), 0, [6; 32], message()),
+			Error::<Test>::NotMaciPoll
+		);
+	});
+}
+
+#[test]
+fn try_publish_too_many_messages() {
+	new_test_ext().execute_with(|| {
+		launch_maci_poll();
+		for _ in 0..16 {
+			assert_ok!(Qv::publish_message(Origin::signed(20), 0, [6; 32], message()));
+		}
+		assert_noop!(
+			Qv::publish_message(Origin::signed(20), 0, [6; 32], message()),
+			Error::<Test>::TooManyMessages
+		);
+	});
+}
+
+fn relayed_vote(voter: u64, nonce: u32, expiry: u64) -> RelayedVote<u64, u64> {
+	RelayedVote { voter, index: 0, aye: true, votes: 10, nonce, expiry }
+}
+
+fn sign(vote: &RelayedVote<u64, u64>) -> TestSignature {
+	TestSignature(vote.voter, Qv::relay_payload(vote))
+}
+
+fn validate_relayed(
+	vote: RelayedVote<u64, u64>,
+	signature: TestSignature,
+) -> Result<(), InvalidTransaction> {
+	let call = crate::Call::<Test>::relay_vote { vote, signature };
+	Qv::validate_unsigned(TransactionSource::External, &call)
+		.map(|_| ())
+		.map_err(|error| match error {
+			TransactionValidityError::Invalid(invalid) => invalid,
+			_ => InvalidTransaction::Call,
+		})
+}
+
+#[test]
+fn relayed_vote_is_cast_for_its_voter() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+		set_verified_identity(Origin::signed(40));
+
+		let vote = relayed_vote(40, 0, DECISION_STARTS + 5);
+		assert_ok!(validate_relayed(vote.clone(), sign(&vote)));
+		assert_noop!(
+			Qv::relay_vote(Origin::signed(20), vote.clone(), sign(&vote)),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Qv::relay_vote(Origin::none(), vote.clone(), sign(&vote)));
+		assert_eq!(last_event(), QvEvent::VoteRelayed { voter: 40, nonce: 0, result: Ok(()) });
+		System::assert_has_event(Event::Qv(QvEvent::VotesCast {
+			aye: true,
+			number_of_votes: 10,
+			index: 0,
+		}));
+		assert_eq!(Balances::free_balance(40), 9_900);
+		assert_eq!(Qv::relay_nonce(40), 1);
+
+		assert_eq!(validate_relayed(vote.clone(), sign(&vote)), Err(InvalidTransaction::Stale));
+		assert_noop!(
+			Qv::relay_vote(Origin::none(), vote.clone(), sign(&vote)),
+			Error::<Test>::StaleRelayNonce
+		);
+	});
+}
+
+#[test]
+fn try_relay_invalid_votes() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+		set_verified_identity(Origin::signed(40));
+
+		let vote = relayed_vote(40, 0, DECISION_STARTS + 5);
+		let forged = TestSignature(41, Qv::relay_payload(&vote));
+		assert_eq!(
+			validate_relayed(vote.clone(), forged.clone()),
+			Err(InvalidTransaction::BadProof)
+		);
+		assert_noop!(
+			Qv::relay_vote(Origin::none(), vote.clone(), forged),
+			Error::<Test>::BadRelaySignature
+		);
+		// The signature is over the vote prefixed with a domain tag and the genesis hash, so a
+		// signature of the bare vote can not be relayed
+		let bare = TestSignature(40, vote.encode());
+		assert_eq!(validate_relayed(vote.clone(), bare.clone()), Err(InvalidTransaction::BadProof));
+		assert_noop!(Qv::relay_vote(Origin::none(), vote, bare), Error::<Test>::BadRelaySignature);
+
+		let expired = relayed_vote(40, 0, DECISION_STARTS - 1);
+		assert_eq!(
+			validate_relayed(expired.clone(), sign(&expired)),
+			Err(InvalidTransaction::Stale)
+		);
+		assert_noop!(
+			Qv::relay_vote(Origin::none(), expired.clone(), sign(&expired)),
+			Error::<Test>::RelayExpired
+		);
+
+		// A vote with a future nonce is valid in the pool, but can not be cast yet
+		let future = relayed_vote(40, 1, DECISION_STARTS + 5);
+		assert_ok!(validate_relayed(future.clone(), sign(&future)));
+		assert_noop!(
+			Qv::relay_vote(Origin::none(), future.clone(), sign(&future)),
+			Error::<Test>::FutureRelayNonce
+		);
+
+		let unverified = relayed_vote(41, 0, DECISION_STARTS + 5);
+		assert_eq!(
+			validate_relayed(unverified.clone(), sign(&unverified)),
+			Err(InvalidTransaction::BadSigner)
+		);
+	});
+}
+
+#[test]
+fn relayed_vote_that_can_not_be_cast_is_invalid() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		set_verified_identity(Origin::signed(30));
+		assert_ok!(initiate_proposal_one(30));
+		set_verified_identity(Origin::signed(40));
+		set_verified_identity(Origin::signed(10));
+
+		// The referendum is not deciding yet
+		let vote = relayed_vote(40, 0, DECISION_STARTS + 5);
+		assert_eq!(validate_relayed(vote.clone(), sign(&vote)), Err(InvalidTransaction::Call));
+
+		for backer in [31, 32] {
+			let launch_voter = Origin::signed(backer);
+			set_verified_identity(launch_voter.clone());
+			assert_ok!(Qv::cast_launch_votes(launch_voter, 500, 0));
+		}
+		run_to(DECISION_STARTS);
+		assert_ok!(validate_relayed(vote.clone(), sign(&vote)));
+
+		// Account 10 can not afford 10 votes besides its identity deposit
+		let unaffordable = relayed_vote(10, 0, DECISION_STARTS + 5);
+		assert_eq!(
+			validate_relayed(unaffordable.clone(), sign(&unaffordable)),
+			Err(InvalidTransaction::Payment)
+		);
+
+		// The voter already voted on the referendum
+		assert_ok!(Qv::vote_aye(Origin::signed(40), 1, 0));
+		assert_eq!(validate_relayed(vote.clone(), sign(&vote)), Err(InvalidTransaction::Call));
+	});
+}
+
+#[test]
+fn failed_relayed_vote_uses_up_the_nonce() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		set_verified_identity(Origin::signed(30));
+		assert_ok!(initiate_proposal_one(30));
+		set_verified_identity(Origin::signed(40));
+
+		let vote = relayed_vote(40, 0, 5);
+		assert_ok!(Qv::relay_vote(Origin::none(), vote.clone(), sign(&vote)));
+		assert_eq!(
+			last_event(),
+			QvEvent::VoteRelayed {
+				voter: 40,
+				nonce: 0,
+				result: Err(Error::<Test>::StillInLaunchPhase.into())
+			}
+		);
+		assert_eq!(Qv::relay_nonce(40), 1);
+		assert_eq!(Balances::free_balance(40), 10_000);
+	});
+}
+
+fn ballot_batch(
+	ballots: Vec<(RelayedVote<u64, u64>, TestSignature)>,
+) -> BoundedVec<(RelayedVote<u64, u64>, TestSignature), ConstU32<4>> {
+	ballots.try_into().unwrap()
+}
+
+#[test]
+fn ballot_batch_charges_every_voter_for_its_own_votes() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+		set_verified_identity(Origin::signed(40));
+		set_verified_identity(Origin::signed(41));
+
+		let first = relayed_vote(40, 0, DECISION_STARTS);
+		let second = RelayedVote { votes: 20, ..relayed_vote(41, 0, DECISION_STARTS) };
+		let ballots =
+			ballot_batch(vec![(first.clone(), sign(&first)), (second.clone(), sign(&second))]);
+		assert_ok!(Qv::submit_ballot_batch(Origin::signed(20), 0, ballots));
+
+		assert_eq!(
+			last_event(),
+			QvEvent::BallotBatchSubmitted { index: 0, results: vec![(40, Ok(())), (41, Ok(()))] }
+		);
+		assert_eq!(Balances::free_balance(40), 9_900);
+		assert_eq!(Balances::free_balance(41), 9_600);
+		assert_eq!(Balances::free_balance(20), 110);
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!((tally.ayes, tally.supporters), (30, 2));
+	});
+}
+
+#[test]
+fn invalid_ballots_do_not_revert_the_batch() {
+	new_test_ext().execute_with(|| {
+		launch_referendum();
+		run_to(DECISION_STARTS);
+		set_verified_identity(Origin::signed(40));
+
+		let valid = relayed_vote(40, 0, DECISION_STARTS);
+		let forged = relayed_vote(1, 0, DECISION_STARTS);
+		let other = RelayedVote { index: 1, ..relayed_vote(2, 0, DECISION_STARTS) };
+		let unverified = relayed_vote(41, 0, DECISION_STARTS);
+		let ballots = ballot_batch(vec![
+			(forged.clone(), TestSignature(2, Qv::relay_payload(&forged))),
+			(valid.clone(), sign(&valid)),
+			(other.clone(), sign(&other)),
+			(unverified.clone(), sign(&unverified)),
+		]);
+		assert_noop!(
+			Qv::submit_ballot_batch(Origin::none(), 0, ballots.clone()),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Qv::submit_ballot_batch(Origin::signed(20), 0, ballots));
+
+		assert_eq!(
+			last_event(),
+			QvEvent::BallotBatchSubmitted {
+				index: 0,
+				results: vec![
+					(1, Err(Error::<Test>::BadRelaySignature.into())),
+					(40, Ok(())),
+					(2, Err(Error::<Test>::BallotForOtherReferendum.into())),
+					(41, Err(Error::<Test>::NoIdentity.into())),
+				],
+			}
+		);
+		assert_eq!(Balances::free_balance(40), 9_900);
+		assert_eq!(Balances::free_balance(41), 10_010);
+		// A ballot uses up its nonce once its signature is checked, even if it is not cast
+		assert_eq!((Qv::relay_nonce(1), Qv::relay_nonce(40), Qv::relay_nonce(41)), (0, 1, 1));
+		let tally = Referenda::referendum_view(0).unwrap().ongoing.unwrap().tally;
+		assert_eq!((tally.ayes, tally.supporters), (10, 1));
 	});
 }
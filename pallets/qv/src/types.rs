@@ -0,0 +1,231 @@
+//! Miscellaneous additional datatypes.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::traits::VoteTally;
+use pallet_referenda::ReferendumIndex;
+use scale_info::TypeInfo;
+use sp_runtime::{Perbill, RuntimeDebug};
+
+/// A tally that quadratically priced aye and nay votes can be counted into.
+///
+/// Implemented by the runtime's `pallet_referenda::Config::Tally` type, e.g. [`QuadraticTally`].
+pub trait AccumulateVotes {
+	/// Count one voter's `votes` in favour.
+	fn add_aye(&mut self, votes: u32);
+	/// Count one voter's `votes` against.
+	fn add_nay(&mut self, votes: u32);
+	/// Stop counting one voter's `votes` that were counted in favour.
+	fn remove_aye(&mut self, votes: u32);
+	/// Stop counting one voter's `votes` that were counted against.
+	fn remove_nay(&mut self, votes: u32);
+	/// Count `ayes` in favour, of `supporters` voters, and `nays` against, of other voters, at
+	/// once. Used for tallies that are counted off chain.
+	fn add_totals(&mut self, ayes: u32, nays: u32, supporters: u32);
+	/// Record the size of the electorate, as deciding on the referendum begins.
+	fn snapshot_electorate(&mut self, _electorate: u32) {}
+}
+
+/// A vote cast by an account in the deciding phase of a referendum.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct AccountVote<Balance> {
+	/// Whether the votes were cast in favour.
+	pub aye: bool,
+	/// The number of votes cast.
+	pub votes: u32,
+	/// The amount reserved for the votes.
+	pub cost: Balance,
+}
+
+/// A deciding phase vote signed by its voter, which anyone may relay to the chain.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct RelayedVote<AccountId, BlockNumber> {
+	/// The account that votes, and signs the vote.
+	pub voter: AccountId,
+	/// The index of the referendum.
+	pub index: ReferendumIndex,
+	/// Whether the votes are cast in favour.
+	pub aye: bool,
+	/// The number of votes cast.
+	pub votes: u32,
+	/// The voter's relay nonce, which the vote uses up.
+	pub nonce: u32,
+	/// The last block in which the vote may be relayed.
+	pub expiry: BlockNumber,
+}
+
+/// A secret ballot committed to by an account in the deciding phase of a referendum, which is
+/// yet to be revealed.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BallotCommitment<Hash, Balance> {
+	/// The hash of the ballot's `(aye, votes, salt)`.
+	pub commitment: Hash,
+	/// The most votes the ballot may reveal.
+	pub max_votes: u32,
+	/// The amount reserved for `max_votes` votes.
+	pub cost: Balance,
+}
+
+/// A referendum whose deciding phase votes are sent as messages encrypted to a coordinator, who
+/// counts them off chain.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct MaciPoll<AccountId, Hash> {
+	/// The account that publishes the tally.
+	pub coordinator: AccountId,
+	/// The coordinator's X25519 public key, which messages are encrypted to.
+	pub public_key: [u8; 32],
+	/// The number of voters that signed up, which is the state index of the next one.
+	pub signups: u32,
+	/// The number of messages published, which is the position of the next one.
+	pub messages: u32,
+	/// The hash chain of all messages published, in order.
+	pub message_root: Hash,
+	/// The tally, once the coordinator published it.
+	pub tally: Option<MaciTally<Hash>>,
+}
+
+/// The tally of a [`MaciPoll`], as published by its coordinator.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct MaciTally<Hash> {
+	/// The number of votes in favour.
+	pub ayes: u32,
+	/// The number of votes against.
+	pub nays: u32,
+	/// The number of voters in favour.
+	pub supporters: u32,
+	/// The commitment to the final voter states, which the tally is counted from.
+	pub commitment: Hash,
+	/// The commitment to the poll's message root and voice credits, the tally and `commitment`.
+	/// As `commitment` is salted with the coordinator's secret key, only whoever holds the key
+	/// can recompute it from the message log with `maci-verifier`.
+	pub tally_commitment: Hash,
+}
+
+/// The launch votes of an account backing a referendum.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BackerRecord<Balance> {
+	/// The number of launch votes cast. Zero for the initiator.
+	pub votes: u32,
+	/// The exact amount reserved for the launch votes.
+	pub cost: Balance,
+	/// Whether the cost is held by pallet-referenda as the Decision Deposit.
+	pub decision_deposit: bool,
+}
+
+/// The aggregated launch backing of a referendum.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct LaunchTally {
+	/// The number of backers, including the initiator.
+	pub backers: u32,
+	/// The total number of launch votes cast.
+	pub votes: u32,
+}
+
+/// What the price of votes is paid in.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum VotePricing {
+	/// Votes reserve `Config::Currency`, which is given back once they no longer count.
+	Currency,
+	/// Votes spend non-transferable voice credits, which are given back once they no longer
+	/// count. Every account with an identity is allotted `Config::VoiceCreditAllotment`.
+	VoiceCredits,
+}
+
+/// The voice credits an account has left to spend on votes in a voice credit epoch.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct VoiceCreditBudget<Balance> {
+	/// The epoch the budget is for.
+	pub epoch: u32,
+	/// The voice credits left.
+	pub left: Balance,
+}
+
+/// Whether the identity behind a claim commitment has been claimed, and by whom.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum ClaimState<AccountId> {
+	/// Nobody has redeemed the claim code yet.
+	Unclaimed,
+	/// The account that redeemed the claim code.
+	Claimed(AccountId),
+}
+
+/// The deciding phase votes of a referendum.
+///
+/// Approval is the share of aye votes in all votes. Support is the share of the electorate that
+/// voted aye, counted in voters rather than in votes, so that it measures turnout. The electorate
+/// is snapshotted when deciding begins, and is zero before.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct QuadraticTally {
+	/// The number of votes in favour.
+	pub ayes: u32,
+	/// The number of votes against.
+	pub nays: u32,
+	/// The number of voters in favour.
+	pub supporters: u32,
+	/// The number of voters that were eligible when deciding began.
+	pub electorate: u32,
+}
+
+impl<Class> VoteTally<u32, Class> for QuadraticTally {
+	fn new(_: Class) -> Self {
+		Self::default()
+	}
+
+	fn ayes(&self, _: Class) -> u32 {
+		self.ayes
+	}
+
+	fn support(&self, _: Class) -> Perbill {
+		Perbill::from_rational(self.supporters, self.electorate.max(1))
+	}
+
+	fn approval(&self, _: Class) -> Perbill {
+		Perbill::from_rational(self.ayes, self.ayes.saturating_add(self.nays).max(1))
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn unanimity(_: Class) -> Self {
+		Self { ayes: 100, nays: 0, supporters: 100, electorate: 100 }
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn rejection(_: Class) -> Self {
+		Self { ayes: 0, nays: 100, supporters: 0, electorate: 100 }
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn from_requirements(support: Perbill, approval: Perbill, _: Class) -> Self {
+		let supporters = support.mul_ceil(100u32);
+		let ayes = approval.mul_ceil(100u32);
+		Self { ayes, nays: 100 - ayes, supporters, electorate: 100 }
+	}
+}
+
+impl AccumulateVotes for QuadraticTally {
+	fn add_aye(&mut self, votes: u32) {
+		self.ayes = self.ayes.saturating_add(votes);
+		self.supporters = self.supporters.saturating_add(1);
+	}
+
+	fn add_nay(&mut self, votes: u32) {
+		self.nays = self.nays.saturating_add(votes);
+	}
+
+	fn remove_aye(&mut self, votes: u32) {
+		self.ayes = self.ayes.saturating_sub(votes);
+		self.supporters = self.supporters.saturating_sub(1);
+	}
+
+	fn remove_nay(&mut self, votes: u32) {
+		self.nays = self.nays.saturating_sub(votes);
+	}
+
+	fn add_totals(&mut self, ayes: u32, nays: u32, supporters: u32) {
+		self.ayes = self.ayes.saturating_add(ayes);
+		self.nays = self.nays.saturating_add(nays);
+		self.supporters = self.supporters.saturating_add(supporters);
+	}
+
+	fn snapshot_electorate(&mut self, electorate: u32) {
+		self.electorate = electorate;
+	}
+}
@@ -18,7 +18,7 @@
 //! Helpers for managing the different weights in various algorithmic branches.
 
 use super::Config;
-use crate::weights::WeightInfo;
+use crate::{weights::WeightInfo, ReferendumHooks};
 
 /// Branches within the `begin_deciding` function.
 pub enum BeginDecidingBranch {
@@ -59,8 +59,11 @@ impl From<BeginDecidingBranch> for ServiceBranch {
 
 impl ServiceBranch {
 	/// Return the weight of the `nudge` function when it takes the branch denoted by `self`.
+	///
+	/// The branches that conclude the referendum include the weight of its conclusion hook.
 	pub fn weight_of_nudge<T: Config<I>, I: 'static>(self) -> frame_support::weights::Weight {
 		use ServiceBranch::*;
+		let hook = T::Hooks::max_conclusion_weight();
 		match self {
 			NoDeposit => T::WeightInfo::nudge_referendum_no_deposit(),
 			Preparing => T::WeightInfo::nudge_referendum_preparing(),
@@ -74,9 +77,9 @@ impl ServiceBranch {
 			ContinueConfirming => T::WeightInfo::nudge_referendum_continue_confirming(),
 			EndConfirming => T::WeightInfo::nudge_referendum_end_confirming(),
 			ContinueNotConfirming => T::WeightInfo::nudge_referendum_continue_not_confirming(),
-			Approved => T::WeightInfo::nudge_referendum_approved(),
-			Rejected => T::WeightInfo::nudge_referendum_rejected(),
-			TimedOut | Fail => T::WeightInfo::nudge_referendum_timed_out(),
+			Approved => T::WeightInfo::nudge_referendum_approved().saturating_add(hook),
+			Rejected => T::WeightInfo::nudge_referendum_rejected().saturating_add(hook),
+			TimedOut | Fail => T::WeightInfo::nudge_referendum_timed_out().saturating_add(hook),
 		}
 	}
 
@@ -97,6 +100,7 @@ impl ServiceBranch {
 			.max(T::WeightInfo::nudge_referendum_approved())
 			.max(T::WeightInfo::nudge_referendum_rejected())
 			.max(T::WeightInfo::nudge_referendum_timed_out())
+			.saturating_add(T::Hooks::max_conclusion_weight())
 	}
 
 	/// Return the weight of the `place_decision_deposit` function when it takes the branch denoted
@@ -132,6 +136,7 @@ impl ServiceBranch {
 			.max(T::WeightInfo::place_decision_deposit_not_queued())
 			.max(T::WeightInfo::place_decision_deposit_passing())
 			.max(T::WeightInfo::place_decision_deposit_failing())
+			.saturating_add(T::Hooks::max_conclusion_weight())
 	}
 }
 
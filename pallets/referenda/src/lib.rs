@@ -93,8 +93,9 @@ pub use self::{
 	pallet::*,
 	types::{
 		BalanceOf, CallOf, Curve, DecidingStatus, DecidingStatusOf, Deposit, InsertSorted,
-		NegativeImbalanceOf, PalletsOriginOf, ReferendumIndex, ReferendumInfo, ReferendumInfoOf,
-		ReferendumStatus, ReferendumStatusOf, ScheduleAddressOf, TallyOf, TrackIdOf, TrackInfo,
+		NegativeImbalanceOf, OngoingView, PalletsOriginOf, ReferendumHooks, ReferendumIndex,
+		ReferendumInfo, ReferendumInfoOf, ReferendumPhase, ReferendumStatus, ReferendumStatusOf,
+		ReferendumView, ReferendumViewOf, ScheduleAddressOf, TallyOf, TrackIdOf, TrackInfo,
 		TrackInfoOf, TracksInfo, VotesOf,
 	},
 	weights::WeightInfo,
@@ -188,6 +189,10 @@ pub mod pallet {
 			Self::BlockNumber,
 			Origin = <Self::Origin as OriginTrait>::PalletsOrigin,
 		>;
+
+		/// Hooks that are called when deciding on a referendum begins, and when a referendum
+		/// concludes.
+		type Hooks: ReferendumHooks<Self::Tally>;
 	}
 
 	/// The next free referendum index, aka the number of referenda started so far.
@@ -360,33 +365,7 @@ pub mod pallet {
 			enactment_moment: DispatchTime<T::BlockNumber>,
 		) -> DispatchResult {
 			let who = T::SubmitOrigin::ensure_origin(origin)?;
-
-			let track =
-				T::Tracks::track_for(&proposal_origin).map_err(|_| Error::<T, I>::NoTrack)?;
-			let submission_deposit = Self::take_deposit(who, T::SubmissionDeposit::get())?;
-			let index = ReferendumCount::<T, I>::mutate(|x| {
-				let r = *x;
-				*x += 1;
-				r
-			});
-			let now = frame_system::Pallet::<T>::block_number();
-			let nudge_call = Call::nudge_referendum { index };
-			let status = ReferendumStatus {
-				track,
-				origin: *proposal_origin,
-				proposal_hash,
-				enactment: enactment_moment,
-				submitted: now,
-				submission_deposit,
-				decision_deposit: None,
-				deciding: None,
-				tally: TallyOf::<T, I>::new(track),
-				in_queue: false,
-				alarm: Self::set_alarm(nudge_call, now.saturating_add(T::UndecidingTimeout::get())),
-			};
-			ReferendumInfoFor::<T, I>::insert(index, ReferendumInfo::Ongoing(status));
-
-			Self::deposit_event(Event::<T, I>::Submitted { index, track, proposal_hash });
+			Self::do_submit(who, *proposal_origin, proposal_hash, enactment_moment, None)?;
 			Ok(())
 		}
 
@@ -471,7 +450,7 @@ pub mod pallet {
 		/// - `index`: The index of the referendum to be cancelled.
 		///
 		/// Emits `Cancelled`.
-		#[pallet::weight(T::WeightInfo::cancel())]
+		#[pallet::weight(T::WeightInfo::cancel().saturating_add(T::Hooks::max_conclusion_weight()))]
 		pub fn cancel(origin: OriginFor<T>, index: ReferendumIndex) -> DispatchResult {
 			T::CancelOrigin::ensure_origin(origin.clone())?;
 			let status = Self::ensure_ongoing(index)?;
@@ -486,7 +465,7 @@ pub mod pallet {
 				status.submission_deposit,
 				status.decision_deposit,
 			);
-			ReferendumInfoFor::<T, I>::insert(index, info);
+			Self::conclude(index, info, T::Hooks::on_cancelled);
 			Ok(())
 		}
 
@@ -496,7 +475,7 @@ pub mod pallet {
 		/// - `index`: The index of the referendum to be cancelled.
 		///
 		/// Emits `Killed` and `DepositSlashed`.
-		#[pallet::weight(T::WeightInfo::kill())]
+		#[pallet::weight(T::WeightInfo::kill().saturating_add(T::Hooks::max_conclusion_weight()))]
 		pub fn kill(origin: OriginFor<T>, index: ReferendumIndex) -> DispatchResult {
 			T::KillOrigin::ensure_origin(origin)?;
 			let status = Self::ensure_ongoing(index)?;
@@ -508,7 +487,7 @@ pub mod pallet {
 			Self::slash_deposit(Some(status.submission_deposit.clone()));
 			Self::slash_deposit(status.decision_deposit.clone());
 			let info = ReferendumInfo::Killed(frame_system::Pallet::<T>::block_number());
-			ReferendumInfoFor::<T, I>::insert(index, info);
+			Self::conclude(index, info, T::Hooks::on_killed);
 			Ok(())
 		}
 
@@ -639,6 +618,7 @@ impl<T: Config<I>, I: 'static> Polling<T::Tally> for Pallet<T, I> {
 			proposal_hash: <T::Hashing as sp_runtime::traits::Hash>::hash_of(&index),
 			enactment: DispatchTime::After(Zero::zero()),
 			submitted: now,
+			deadline: None,
 			submission_deposit: Deposit { who: dummy_account_id, amount: Zero::zero() },
 			decision_deposit: None,
 			deciding: None,
@@ -688,6 +668,50 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 		}
 	}
 
+	/// Propose a referendum on behalf of `who`, who must have `SubmissionDeposit` funds available.
+	///
+	/// Does the work of `submit` once the origin has been checked, and returns the index that was
+	/// assigned to the new referendum. If a `deadline` is given, the referendum must be approved
+	/// by then: it is rejected if it is being decided at that point and timed out otherwise.
+	///
+	/// Emits `Submitted`.
+	pub fn do_submit(
+		who: T::AccountId,
+		proposal_origin: PalletsOriginOf<T>,
+		proposal_hash: T::Hash,
+		enactment_moment: DispatchTime<T::BlockNumber>,
+		deadline: Option<T::BlockNumber>,
+	) -> Result<ReferendumIndex, DispatchError> {
+		let track = T::Tracks::track_for(&proposal_origin).map_err(|_| Error::<T, I>::NoTrack)?;
+		let submission_deposit = Self::take_deposit(who, T::SubmissionDeposit::get())?;
+		let index = ReferendumCount::<T, I>::mutate(|x| {
+			let r = *x;
+			*x += 1;
+			r
+		});
+		let now = frame_system::Pallet::<T>::block_number();
+		let nudge_call = Call::nudge_referendum { index };
+		let timeout = now.saturating_add(T::UndecidingTimeout::get());
+		let status = ReferendumStatus {
+			track,
+			origin: proposal_origin,
+			proposal_hash,
+			enactment: enactment_moment,
+			submitted: now,
+			deadline,
+			submission_deposit,
+			decision_deposit: None,
+			deciding: None,
+			tally: TallyOf::<T, I>::new(track),
+			in_queue: false,
+			alarm: Self::set_alarm(nudge_call, deadline.map_or(timeout, |d| d.min(timeout))),
+		};
+		ReferendumInfoFor::<T, I>::insert(index, ReferendumInfo::Ongoing(status));
+
+		Self::deposit_event(Event::<T, I>::Submitted { index, track, proposal_hash });
+		Ok(index)
+	}
+
 	pub fn is_ongoing(index: ReferendumIndex) -> bool {
 		match ReferendumInfoFor::<T, I>::get(index) {
 			Some(ReferendumInfo::Ongoing(_)) => true,
@@ -695,6 +719,62 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 		}
 	}
 
+	/// Return a read-only view of referendum `index`, or `None` if there is no such referendum.
+	pub fn referendum_view(index: ReferendumIndex) -> Option<ReferendumViewOf<T, I>> {
+		let concluded =
+			|phase, when, submission_deposit, decision_deposit| -> ReferendumViewOf<T, I> {
+				ReferendumView {
+					phase,
+					concluded: Some(when),
+					submission_deposit,
+					decision_deposit,
+					ongoing: None,
+				}
+			};
+		Some(match ReferendumInfoFor::<T, I>::get(index)? {
+			ReferendumInfo::Ongoing(status) => {
+				let track = Self::track(status.track)?;
+				let phase = match status.deciding {
+					Some(DecidingStatus { confirming: Some(_), .. }) => ReferendumPhase::Confirming,
+					Some(_) => ReferendumPhase::Deciding,
+					None if status.in_queue => ReferendumPhase::Queued,
+					None => ReferendumPhase::Preparing,
+				};
+				let deciding_since = status.deciding.as_ref().map(|d| d.since);
+				ReferendumView {
+					phase,
+					concluded: None,
+					submission_deposit: Some(status.submission_deposit),
+					decision_deposit: status.decision_deposit,
+					ongoing: Some(OngoingView {
+						track: status.track,
+						proposal_hash: status.proposal_hash,
+						submitted: status.submitted,
+						tally: status.tally,
+						prepare_end: status.submitted.saturating_add(track.prepare_period),
+						undeciding_timeout: status
+							.submitted
+							.saturating_add(T::UndecidingTimeout::get()),
+						deadline: status.deadline,
+						deciding_since,
+						decision_end: deciding_since
+							.map(|since| since.saturating_add(track.decision_period)),
+						confirming_until: status.deciding.and_then(|d| d.confirming),
+					}),
+				}
+			},
+			ReferendumInfo::Approved(when, s, d) =>
+				concluded(ReferendumPhase::Approved, when, Some(s), d),
+			ReferendumInfo::Rejected(when, s, d) =>
+				concluded(ReferendumPhase::Rejected, when, Some(s), d),
+			ReferendumInfo::Cancelled(when, s, d) =>
+				concluded(ReferendumPhase::Cancelled, when, Some(s), d),
+			ReferendumInfo::TimedOut(when, s, d) =>
+				concluded(ReferendumPhase::TimedOut, when, Some(s), d),
+			ReferendumInfo::Killed(when) => concluded(ReferendumPhase::Killed, when, None, None),
+		})
+	}
+
 	// Enqueue a proposal from a referendum which has presumably passed.
 	fn schedule_enactment(
 		index: ReferendumIndex,
@@ -755,6 +835,7 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 		now: T::BlockNumber,
 		track: &TrackInfoOf<T, I>,
 	) -> (Option<T::BlockNumber>, BeginDecidingBranch) {
+		T::Hooks::on_decision_started(index, &mut status.tally);
 		let is_passing = Self::is_passing(
 			&status.tally,
 			Zero::zero(),
@@ -901,6 +982,9 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 			None => return (ReferendumInfo::Ongoing(status), false, ServiceBranch::Fail),
 		};
 		let timeout = status.submitted + T::UndecidingTimeout::get();
+		// A deadline cuts both the submission timeout and the decision short.
+		let timeout = status.deadline.map_or(timeout, |d| d.min(timeout));
+		let past_deadline = status.deadline.map_or(false, |d| now >= d);
 		// Default the alarm to the submission timeout.
 		let mut alarm = timeout;
 		let branch;
@@ -951,26 +1035,28 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 					// Too long without being decided - end it.
 					Self::ensure_no_alarm(&mut status);
 					Self::deposit_event(Event::<T, I>::TimedOut { index, tally: status.tally });
+					let info = ReferendumInfo::TimedOut(
+						now,
+						status.submission_deposit,
+						status.decision_deposit,
+					);
 					return (
-						ReferendumInfo::TimedOut(
-							now,
-							status.submission_deposit,
-							status.decision_deposit,
-						),
+						Self::conclude(index, info, T::Hooks::on_timed_out),
 						true,
 						ServiceBranch::TimedOut,
 					)
 				}
 			},
 			Some(deciding) => {
-				let is_passing = Self::is_passing(
-					&status.tally,
-					now.saturating_sub(deciding.since),
-					track.decision_period,
-					&track.min_support,
-					&track.min_approval,
-					status.track,
-				);
+				let is_passing = !past_deadline &&
+					Self::is_passing(
+						&status.tally,
+						now.saturating_sub(deciding.since),
+						track.decision_period,
+						&track.min_support,
+						&track.min_approval,
+						status.track,
+					);
 				branch = if is_passing {
 					match deciding.confirming {
 						Some(t) if now >= t => {
@@ -989,12 +1075,13 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 								index,
 								tally: status.tally,
 							});
+							let info = ReferendumInfo::Approved(
+								now,
+								status.submission_deposit,
+								status.decision_deposit,
+							);
 							return (
-								ReferendumInfo::Approved(
-									now,
-									status.submission_deposit,
-									status.decision_deposit,
-								),
+								Self::conclude(index, info, T::Hooks::on_approved),
 								true,
 								ServiceBranch::Approved,
 							)
@@ -1009,17 +1096,19 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 						},
 					}
 				} else {
-					if now >= deciding.since.saturating_add(track.decision_period) {
+					let decision_end = deciding.since.saturating_add(track.decision_period);
+					if past_deadline || now >= decision_end {
 						// Failed!
 						Self::ensure_no_alarm(&mut status);
 						Self::note_one_fewer_deciding(status.track);
 						Self::deposit_event(Event::<T, I>::Rejected { index, tally: status.tally });
+						let info = ReferendumInfo::Rejected(
+							now,
+							status.submission_deposit,
+							status.decision_deposit,
+						);
 						return (
-							ReferendumInfo::Rejected(
-								now,
-								status.submission_deposit,
-								status.decision_deposit,
-							),
+							Self::conclude(index, info, T::Hooks::on_rejected),
 							true,
 							ServiceBranch::Rejected,
 						)
@@ -1035,6 +1124,9 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 					}
 				};
 				alarm = Self::decision_time(deciding, &status.tally, status.track, track);
+				if let Some(deadline) = status.deadline {
+					alarm = alarm.min(deadline);
+				}
 			},
 		}
 
@@ -1042,6 +1134,20 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 		(ReferendumInfo::Ongoing(status), dirty_alarm || dirty, branch)
 	}
 
+	/// Store the concluded `info` of referendum `index` and call `hook` on it.
+	///
+	/// Returns the info as stored after `hook` ran, since the hook may have altered it, e.g. by
+	/// refunding the Decision Deposit.
+	fn conclude(
+		index: ReferendumIndex,
+		info: ReferendumInfoOf<T, I>,
+		hook: fn(ReferendumIndex),
+	) -> ReferendumInfoOf<T, I> {
+		ReferendumInfoFor::<T, I>::insert(index, &info);
+		hook(index);
+		ReferendumInfoFor::<T, I>::get(index).unwrap_or(info)
+	}
+
 	/// Determine the point at which a referendum will be accepted, move into confirmation with the
 	/// given `tally` or end with rejection (whichever happens sooner).
 	fn decision_time(
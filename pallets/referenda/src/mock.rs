@@ -226,6 +226,7 @@ impl Config for Test {
 	type UndecidingTimeout = ConstU64<20>;
 	type AlarmInterval = AlarmInterval;
 	type Tracks = TestTracksInfo;
+	type Hooks = ();
 }
 
 pub fn new_test_ext() -> sp_io::TestExternalities {
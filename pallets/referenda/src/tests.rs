@@ -73,6 +73,114 @@ fn basic_happy_path_works() {
 	});
 }
 
+#[test]
+fn referendum_view_follows_the_lifecycle() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Referenda::referendum_view(0), None);
+		assert_ok!(Referenda::submit(
+			Origin::signed(1),
+			Box::new(RawOrigin::Root.into()),
+			set_balance_proposal_hash(1),
+			DispatchTime::At(10),
+		));
+		let view = Referenda::referendum_view(0).unwrap();
+		assert_eq!(view.phase, ReferendumPhase::Preparing);
+		assert_eq!(view.concluded, None);
+		assert_eq!(view.submission_deposit, Some(Deposit { who: 1, amount: 2 }));
+		assert_eq!(view.decision_deposit, None);
+		assert_eq!(
+			view.ongoing,
+			Some(OngoingView {
+				track: 0,
+				proposal_hash: set_balance_proposal_hash(1),
+				submitted: 1,
+				tally: Tally { ayes: 0, nays: 0 },
+				prepare_end: 5,
+				undeciding_timeout: 21,
+				deadline: None,
+				deciding_since: None,
+				decision_end: None,
+				confirming_until: None,
+			})
+		);
+
+		assert_ok!(Referenda::place_decision_deposit(Origin::signed(2), 0));
+		run_to(5);
+		let view = Referenda::referendum_view(0).unwrap();
+		assert_eq!(view.phase, ReferendumPhase::Deciding);
+		assert_eq!(view.decision_deposit, Some(Deposit { who: 2, amount: 10 }));
+		let ongoing = view.ongoing.unwrap();
+		assert_eq!(ongoing.deciding_since, Some(5));
+		assert_eq!(ongoing.decision_end, Some(9));
+
+		set_tally(0, 100, 0);
+		run_to(7);
+		let view = Referenda::referendum_view(0).unwrap();
+		assert_eq!(view.phase, ReferendumPhase::Confirming);
+		assert_eq!(view.ongoing.unwrap().tally, Tally { ayes: 100, nays: 0 });
+		assert_eq!(confirming_until(0), 9);
+
+		run_to(9);
+		assert_eq!(
+			Referenda::referendum_view(0),
+			Some(ReferendumView {
+				phase: ReferendumPhase::Approved,
+				concluded: Some(9),
+				submission_deposit: Some(Deposit { who: 1, amount: 2 }),
+				decision_deposit: Some(Deposit { who: 2, amount: 10 }),
+				ongoing: None,
+			})
+		);
+	});
+}
+
+#[test]
+fn deadline_rejects_referendum_being_decided() {
+	new_test_ext().execute_with(|| {
+		let index = Referenda::do_submit(
+			1,
+			RawOrigin::Root.into(),
+			set_balance_proposal_hash(1),
+			DispatchTime::At(10),
+			Some(7),
+		)
+		.unwrap();
+		assert_ok!(Referenda::place_decision_deposit(Origin::signed(2), index));
+		run_to(5);
+		assert_eq!(deciding_and_failing_since(index), 5);
+		run_to(6);
+		assert!(Referenda::is_ongoing(index));
+		// The decision period would only end at block 9.
+		run_to(7);
+		assert_matches!(
+			ReferendumInfoFor::<Test>::get(index),
+			Some(ReferendumInfo::Rejected(7, ..))
+		);
+	});
+}
+
+#[test]
+fn deadline_times_out_undecided_referendum() {
+	new_test_ext().execute_with(|| {
+		let index = Referenda::do_submit(
+			1,
+			RawOrigin::Root.into(),
+			set_balance_proposal_hash(1),
+			DispatchTime::At(10),
+			Some(3),
+		)
+		.unwrap();
+		assert_eq!(Referenda::referendum_view(index).unwrap().ongoing.unwrap().deadline, Some(3));
+		run_to(2);
+		assert!(Referenda::is_ongoing(index));
+		run_to(3);
+		assert_matches!(
+			ReferendumInfoFor::<Test>::get(index),
+			Some(ReferendumInfo::TimedOut(3, ..))
+		);
+	});
+}
+
 #[test]
 fn insta_confirm_then_kill_works() {
 	new_test_ext().execute_with(|| {
@@ -318,6 +426,7 @@ fn tracks_are_distinguished() {
 						proposal_hash: set_balance_proposal_hash(1),
 						enactment: DispatchTime::At(10),
 						submitted: 1,
+						deadline: None,
 						submission_deposit: Deposit { who: 1, amount: 2 },
 						decision_deposit: Some(Deposit { who: 3, amount: 10 }),
 						deciding: None,
@@ -334,6 +443,7 @@ fn tracks_are_distinguished() {
 						proposal_hash: set_balance_proposal_hash(2),
 						enactment: DispatchTime::At(20),
 						submitted: 1,
+						deadline: None,
 						submission_deposit: Deposit { who: 2, amount: 2 },
 						decision_deposit: Some(Deposit { who: 4, amount: 1 }),
 						deciding: None,
@@ -19,7 +19,7 @@
 
 use super::*;
 use codec::{Decode, Encode, EncodeLike, MaxEncodedLen};
-use frame_support::{traits::schedule::Anon, Parameter};
+use frame_support::{traits::schedule::Anon, weights::Weight, Parameter};
 use scale_info::TypeInfo;
 use sp_arithmetic::{Rounding::*, SignedRounding::*};
 use sp_runtime::{FixedI64, PerThing, RuntimeDebug};
@@ -54,6 +54,14 @@ pub type ReferendumStatusOf<T, I> = ReferendumStatus<
 	<T as frame_system::Config>::AccountId,
 	ScheduleAddressOf<T, I>,
 >;
+pub type ReferendumViewOf<T, I> = ReferendumView<
+	TrackIdOf<T, I>,
+	<T as frame_system::Config>::BlockNumber,
+	<T as frame_system::Config>::Hash,
+	BalanceOf<T, I>,
+	TallyOf<T, I>,
+	<T as frame_system::Config>::AccountId,
+>;
 pub type DecidingStatusOf<T> = DecidingStatus<<T as frame_system::Config>::BlockNumber>;
 pub type TrackInfoOf<T, I = ()> =
 	TrackInfo<BalanceOf<T, I>, <T as frame_system::Config>::BlockNumber>;
@@ -153,6 +161,34 @@ pub trait TracksInfo<Balance, Moment> {
 	}
 }
 
+/// Hooks that are called when deciding on a referendum begins, and when a referendum concludes.
+///
+/// Each conclusion hook is called after the concluded `ReferendumInfo` has been stored, so
+/// implementations may act on it, e.g. by refunding the Decision Deposit.
+pub trait ReferendumHooks<Tally> {
+	/// Deciding on referendum `index` begins. Called before `tally` is first weighed against the
+	/// track's curves, so implementations may complete it, e.g. with a snapshot of the
+	/// electorate.
+	fn on_decision_started(_index: ReferendumIndex, _tally: &mut Tally) {}
+	/// Referendum `index` was approved and its proposal has been scheduled.
+	fn on_approved(_index: ReferendumIndex) {}
+	/// Referendum `index` was rejected.
+	fn on_rejected(_index: ReferendumIndex) {}
+	/// Referendum `index` timed out without being decided.
+	fn on_timed_out(_index: ReferendumIndex) {}
+	/// Referendum `index` was cancelled.
+	fn on_cancelled(_index: ReferendumIndex) {}
+	/// Referendum `index` was killed and its deposits slashed.
+	fn on_killed(_index: ReferendumIndex) {}
+	/// An upper bound on the weight of any one of the conclusion hooks, which is accounted for
+	/// by the calls that may conclude a referendum.
+	fn max_conclusion_weight() -> Weight {
+		0
+	}
+}
+
+impl<Tally> ReferendumHooks<Tally> for () {}
+
 /// Info regarding an ongoing referendum.
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub struct ReferendumStatus<
@@ -176,6 +212,9 @@ pub struct ReferendumStatus<
 	/// The time of submission. Once `UndecidingTimeout` passes, it may be closed by anyone if it
 	/// `deciding` is `None`.
 	pub(crate) submitted: Moment,
+	/// The time by which the referendum must be approved, if any. Once it passes, the referendum
+	/// is rejected if it is being decided and timed out otherwise.
+	pub(crate) deadline: Option<Moment>,
 	/// The deposit reserved for the submission of this referendum.
 	pub(crate) submission_deposit: Deposit<AccountId, Balance>,
 	/// The deposit reserved for this referendum to be decided.
@@ -245,6 +284,72 @@ impl<
 	}
 }
 
+/// The phase of a referendum, present or past.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum ReferendumPhase {
+	/// Submitted and waiting for its Preparation Period to end or its Decision Deposit.
+	Preparing,
+	/// Ready to be decided, but waiting for a free deciding slot on its track.
+	Queued,
+	/// Being decided.
+	Deciding,
+	/// Being decided and in the confirmation stage.
+	Confirming,
+	/// Finished with approval.
+	Approved,
+	/// Finished with rejection.
+	Rejected,
+	/// Finished with cancelation.
+	Cancelled,
+	/// Finished without ever being decided.
+	TimedOut,
+	/// Finished with a kill.
+	Killed,
+}
+
+/// Read-only details of an ongoing referendum.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct OngoingView<TrackId, Moment, Hash, Tally> {
+	/// The track of this referendum.
+	pub track: TrackId,
+	/// The hash of the proposal up for referendum.
+	pub proposal_hash: Hash,
+	/// The time of submission.
+	pub submitted: Moment,
+	/// The current tally of votes in this referendum.
+	pub tally: Tally,
+	/// The end of the Preparation Period.
+	pub prepare_end: Moment,
+	/// The time at which the referendum times out unless it has begun being decided.
+	pub undeciding_timeout: Moment,
+	/// The time by which the referendum must be approved, if any.
+	pub deadline: Option<Moment>,
+	/// When this referendum began being decided, if it has.
+	pub deciding_since: Option<Moment>,
+	/// The end of the Decision Period, if being decided.
+	pub decision_end: Option<Moment>,
+	/// The end of the confirmation stage, if confirming.
+	pub confirming_until: Option<Moment>,
+}
+
+/// A read-only view of a referendum, present or past.
+///
+/// Unlike `ReferendumInfo`, all of its fields are public, so that other pallets and runtime APIs
+/// can inspect a referendum without depending on its storage layout.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ReferendumView<TrackId, Moment, Hash, Balance, Tally, AccountId> {
+	/// The phase the referendum is in.
+	pub phase: ReferendumPhase,
+	/// When the referendum finished, or `None` if it is ongoing.
+	pub concluded: Option<Moment>,
+	/// The submission deposit, if it is still held.
+	pub submission_deposit: Option<Deposit<AccountId, Balance>>,
+	/// The Decision Deposit, if it is placed and not yet refunded.
+	pub decision_deposit: Option<Deposit<AccountId, Balance>>,
+	/// The details of the referendum, or `None` if it has finished.
+	pub ongoing: Option<OngoingView<TrackId, Moment, Hash, Tally>>,
+}
+
 /// Type for describing a curve over the 2-dimensional space of axes between 0-1, as represented
 /// by `(Perbill, Perbill)`.
 #[derive(Clone, Eq, PartialEq, Encode, Decode, TypeInfo, MaxEncodedLen)]
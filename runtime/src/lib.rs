@@ -15,7 +15,7 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
-	transaction_validity::{TransactionSource, TransactionValidity},
+	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
 use sp_std::prelude::*;
@@ -23,7 +23,11 @@ use sp_std::prelude::*;
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 
-use frame_system::EnsureRoot;
+use frame_support::traits::{ConstBool, Contains, EqualPrivilegeOnly, OriginTrait};
+use frame_system::{EnsureNever, EnsureRoot};
+use pallet_identity::RegistrarIndex;
+use pallet_qv::{EligibleVoters, Quadratic, QuadraticTally, VotePricing};
+use pallet_referenda::{Curve, TrackInfo};
 
 // A few exports that help ease life for downstream crates.
 pub use frame_support::{
@@ -146,11 +150,27 @@ parameter_types! {
 	pub const SS58Prefix: u8 = 42;
 }
 
+/// Keeps accounts from submitting referenda, or placing their Decision Deposits, without going
+/// through pallet-qv's launch phase.
+pub struct BaseCallFilter;
+impl Contains<Call> for BaseCallFilter {
+	fn contains(call: &Call) -> bool {
+		!matches!(
+			call,
+			Call::Referenda(
+				pallet_referenda::Call::submit { .. } |
+					pallet_referenda::Call::place_decision_deposit { .. } |
+					pallet_referenda::Call::place_triggering_decision_deposit { .. }
+			)
+		)
+	}
+}
+
 // Configure FRAME pallets to include in runtime.
 
 impl frame_system::Config for Runtime {
 	/// The basic call filter to use in dispatchable.
-	type BaseCallFilter = frame_support::traits::Everything;
+	type BaseCallFilter = BaseCallFilter;
 	/// Block & extrinsics weights: base values and limits.
 	type BlockWeights = BlockWeights;
 	/// The maximum length of a block (in bytes).
@@ -236,6 +256,9 @@ impl pallet_timestamp::Config for Runtime {
 	type WeightInfo = ();
 }
 
+/// The smallest balance an account may have.
+pub const EXISTENTIAL_DEPOSIT: Balance = 500;
+
 impl pallet_balances::Config for Runtime {
 	type MaxLocks = ConstU32<50>;
 	type MaxReserves = ();
@@ -245,7 +268,7 @@ impl pallet_balances::Config for Runtime {
 	/// The ubiquitous event type.
 	type Event = Event;
 	type DustRemoval = ();
-	type ExistentialDeposit = ConstU128<500>;
+	type ExistentialDeposit = ConstU128<EXISTENTIAL_DEPOSIT>;
 	type AccountStore = System;
 	type WeightInfo = pallet_balances::weights::SubstrateWeight<Runtime>;
 }
@@ -292,12 +315,44 @@ impl pallet_identity::Config for Runtime {
 	type WeightInfo = (); // WeightInfo;
 }
 
-pub struct TestTracksInfo;
-impl TracksInfo<u64, u64> for TestTracksInfo {
+impl pallet_preimage::Config for Runtime {
+	type Event = Event;
+	type WeightInfo = pallet_preimage::weights::SubstrateWeight<Runtime>;
+	type Currency = Balances;
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	type MaxSize = ConstU32<{ 4 * 1024 * 1024 }>;
+	type BaseDeposit = ConstU128<{ 1_000 * EXISTENTIAL_DEPOSIT }>;
+	type ByteDeposit = ConstU128<EXISTENTIAL_DEPOSIT>;
+}
+
+parameter_types! {
+	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * BlockWeights::get().max_block;
+}
+
+impl pallet_scheduler::Config for Runtime {
+	type Event = Event;
+	type Origin = Origin;
+	type PalletsOrigin = OriginCaller;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRoot<AccountId>;
+	type MaxScheduledPerBlock = ConstU32<50>;
+	type WeightInfo = pallet_scheduler::weights::SubstrateWeight<Runtime>;
+	type OriginPrivilegeCmp = EqualPrivilegeOnly;
+	type PreimageProvider = Preimage;
+	type NoPreimagePostponement = ConstU32<10>;
+}
+
+/// The referendum tracks. The periods are short, so that a referendum can be followed from
+/// initiation to enactment on a `--dev` node.
+///
+/// Support is the turnout of aye voters among the identities claimed when deciding began.
+pub struct Tracks;
+impl pallet_referenda::TracksInfo<Balance, BlockNumber> for Tracks {
 	type Id = u8;
 	type Origin = <Origin as OriginTrait>::PalletsOrigin;
-	fn tracks() -> &'static [(Self::Id, TrackInfo<u64, u64>)] {
-		static DATA: [(u8, TrackInfo<u64, u64>); 2] = [
+	fn tracks() -> &'static [(Self::Id, TrackInfo<Balance, BlockNumber>)] {
+		static DATA: [(u8, TrackInfo<Balance, BlockNumber>); 2] = [
 			(
 				0u8,
 				TrackInfo {
@@ -359,28 +414,74 @@ impl TracksInfo<u64, u64> for TestTracksInfo {
 }
 
 impl pallet_referenda::Config for Runtime {
-	type WeightInfo = ();
+	type WeightInfo = pallet_referenda::weights::SubstrateWeight<Runtime>;
 	type Call = Call;
 	type Event = Event;
 	type Scheduler = Scheduler;
-	type Currency = pallet_balances::Pallet<Self>;
-	type SubmitOrigin = frame_system::EnsureRoot<AccountId>;
+	type Currency = Balances;
+	// Referenda are only submitted through pallet-qv, which does not go through this origin
+	type SubmitOrigin = EnsureNever<AccountId>;
 	type CancelOrigin = EnsureRoot<AccountId>;
 	type KillOrigin = EnsureRoot<AccountId>;
 	type Slash = ();
 	type Votes = u32;
-	type Tally = Tally;
-	type SubmissionDeposit = ConstU64<2>;
+	type Tally = QuadraticTally;
+	type SubmissionDeposit = ConstU128<2>;
 	type MaxQueued = ConstU32<3>;
-	type UndecidingTimeout = ConstU64<20>;
-	type AlarmInterval = AlarmInterval;
-	type Tracks = TestTracksInfo;
+	type UndecidingTimeout = ConstU32<20>;
+	type AlarmInterval = ConstU32<1>;
+	type Tracks = Tracks;
+	type Hooks = QuadraticVoting;
+}
+
+parameter_types! {
+	pub const VotePricingMode: VotePricing = VotePricing::Currency;
+	pub const CarryOver: Perbill = Perbill::from_percent(10);
+	pub TrustedRegistrars: Vec<RegistrarIndex> = vec![0];
+	pub const RelayedVotePriority: TransactionPriority = TransactionPriority::MAX / 2;
 }
 
 /// Configure the pallet-qv in pallets/qv.
 impl pallet_qv::Config for Runtime {
 	type Event = Event;
+	type TrustedRegistrars = TrustedRegistrars;
+	type IdentityOrigin = EnsureRoot<AccountId>;
 	type Currency = Balances;
+	type Pricing = VotePricingMode;
+	type VoiceCreditAllotment = ConstU128<10_000>;
+	// A voting season per quarter
+	type EpochLength = ConstU32<{ 91 * DAYS }>;
+	type CarryOver = CarryOver;
+	type MaxCarryOver = ConstU128<1_000>;
+	type LaunchDeposit = ConstU32<10>;
+	type VoteCost = Quadratic;
+	type MaxVotesPerCall = ConstU32<10_000>;
+	type MaxBackers = ConstU32<1_000>;
+	type ReferendumLifetime = ConstU32<20>;
+	type MinReferendumLifetime = ConstU32<10>;
+	type MaxReferendumLifetime = ConstU32<{ 365 * DAYS }>;
+	type AllowResubmission = ConstBool<true>;
+	type ResubmissionCooldown = ConstU32<10>;
+	type CommitReveal = ConstBool<false>;
+	type CommitPeriod = ConstU32<2>;
+	type RevealPeriod = ConstU32<2>;
+	type ForfeitUnrevealed = ConstBool<false>;
+	// Anonymous ballots stay off until a zero-knowledge membership verifier is available, since
+	// `MerkleMembership` proofs tell which registered account cast a ballot
+	type Membership = ();
+	type MerkleDepth = ConstU32<20>;
+	type MaciOrigin = EnsureRoot<AccountId>;
+	type MaxMessageLen = ConstU32<256>;
+	type MaxMessages = ConstU32<10_000>;
+	type MaciVoiceCredits = ConstU32<10_000>;
+	type OffchainSignature = Signature;
+	type OffchainPublic = <Signature as Verify>::Signer;
+	type RelayedVotePriority = RelayedVotePriority;
+	type MaxBallotsPerBatch = ConstU32<500>;
+	type Slash = ();
+	type PollTally = QuadraticTally;
+	type Electorate = EligibleVoters<Runtime>;
+	type Polls = Referenda;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -400,6 +501,9 @@ construct_runtime!(
 		TransactionPayment: pallet_transaction_payment,
 		Sudo: pallet_sudo,
 		IdentityVer: pallet_identity,
+		Preimage: pallet_preimage,
+		Scheduler: pallet_scheduler,
+		Referenda: pallet_referenda,
 		// Include the custom logic from the pallet-qv in the runtime.
 		QuadraticVoting: pallet_qv,
 	}